@@ -0,0 +1,5 @@
+mod components;
+mod systems;
+
+pub use components::*;
+pub use systems::*;
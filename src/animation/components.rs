@@ -0,0 +1,101 @@
+use amethyst::ecs::prelude::{Component, DenseVecStorage, NullStorage};
+
+/// Cycles the sprite of an entity through a list of frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    // sprite number and how many seconds it's shown
+    frames: Vec<(usize, f32)>,
+    looping: bool,
+    elapsed: f32,
+    // what's shown once an animation which doesn't loop is over
+    rest: Option<usize>,
+}
+
+impl Animation {
+    pub fn looping(frames: Vec<(usize, f32)>) -> Self {
+        Self {
+            frames,
+            looping: true,
+            elapsed: 0.0,
+            rest: None,
+        }
+    }
+
+    /// An animation which is played once and then stays on its last frame.
+    pub fn once(frames: Vec<(usize, f32)>) -> Self {
+        Self::looping(frames).played_once()
+    }
+
+    /// The frames of a Tiled tile animation, which are tile ids of the same
+    /// tileset shown for some milliseconds.
+    pub fn from_tiled(frames: &[tiled::Frame]) -> Self {
+        Self::looping(
+            frames
+                .iter()
+                .map(|f| (f.tile_id as usize, f.duration as f32 / 1000.0))
+                .collect(),
+        )
+    }
+
+    /// Makes the animation stop on its last frame instead of looping.
+    pub fn played_once(self) -> Self {
+        Self {
+            looping: false,
+            ..self
+        }
+    }
+
+    /// Makes the animation play once, and then show `sprite`.
+    pub fn then_show(self, sprite: usize) -> Self {
+        Self {
+            rest: Some(sprite),
+            ..self.played_once()
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|(_, secs)| secs).sum()
+    }
+
+    pub fn is_done(&self) -> bool {
+        !self.looping && self.elapsed >= self.duration()
+    }
+
+    pub fn advance(&mut self, secs: f32) {
+        self.elapsed += secs;
+        let duration = self.duration();
+        if self.looping && duration > 0.0 {
+            self.elapsed %= duration;
+        }
+    }
+
+    /// The sprite to show at this point of the animation.
+    pub fn sprite(&self) -> usize {
+        if self.is_done() {
+            if let Some(rest) = self.rest {
+                return rest;
+            }
+        }
+        let mut left = self.elapsed;
+        for &(sprite, secs) in self.frames.iter() {
+            if left < secs {
+                return sprite;
+            }
+            left -= secs;
+        }
+        self.frames.last().map_or(0, |&(sprite, _)| sprite)
+    }
+}
+
+impl Component for Animation {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Marks entities which go away once their animation is over, like dying
+/// runners.
+#[derive(Default)]
+pub struct Dying;
+
+impl Component for Dying {
+    type Storage = NullStorage<Self>;
+}
@@ -0,0 +1,54 @@
+use amethyst::core::timing::Time;
+use amethyst::derive::SystemDesc;
+use amethyst::ecs::{Entities, Join, Read, ReadStorage, System, SystemData, WriteStorage};
+use amethyst::renderer::SpriteRender;
+
+use crate::{
+    animation::{Animation, Dying},
+    sim::Simulation,
+    GameState,
+};
+
+/// Plays the animations of tiles, runners, towers and missles, and removes
+/// dying entities once their animation is over.
+#[derive(SystemDesc)]
+pub struct AnimationSystem;
+
+impl<'s> System<'s> for AnimationSystem {
+    type SystemData = (
+        Option<Read<'s, Simulation>>,
+        WriteStorage<'s, Animation>,
+        WriteStorage<'s, SpriteRender>,
+        ReadStorage<'s, Dying>,
+        Read<'s, Time>,
+        Read<'s, GameState>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (sim, mut animations, mut sprites, dying, time, state, entities): Self::SystemData,
+    ) {
+        if *state != GameState::Game {
+            return;
+        }
+        // animations keep up with the simulation when it's sped up
+        let speed = sim.map_or(1, |sim| sim.speed());
+        let secs = time.delta_seconds() * speed as f32;
+        let mut done = vec![];
+        for (ent, animation, sprite) in (&entities, &mut animations, &mut sprites).join() {
+            animation.advance(secs);
+            sprite.sprite_number = animation.sprite();
+            if animation.is_done() {
+                done.push(ent);
+            }
+        }
+        for ent in done {
+            if dying.contains(ent) {
+                entities.delete(ent).unwrap();
+            } else {
+                animations.remove(ent);
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Plays levels without a window, as fast as possible, and reports how they
+//! went. Useful to balance towers and runners across many seeds:
+//!
+//! ```bash
+//! cargo run --bin td-sim -- assets/tower-def.tmx --script towers.ron --runs 100
+//! ```
+//!
+//! The script is a list of commands and the tick they are applied at, just
+//! like the commands of a replay. With `--bot` a bot plays instead, and
+//! `td-sim` fails unless it wins every game, which tells whether a level can
+//! be beaten.
+//!
+//! `td-sim validate <level.tmx>...` only checks that levels can be played at
+//! all, and lists what is wrong with those which can't.
+use tower_def::sim::{
+    load_level, Archetypes, Bot, Command, Level, LevelLoadError, Simulation, Waves, DT,
+};
+
+use std::path::{Path, PathBuf};
+
+/// Games which take longer than this (an hour) are stopped.
+const MAX_TICKS: u64 = 60 * 60 * 60;
+
+/// How often the gold of the player is reported, in ticks.
+const GOLD_INTERVAL: u64 = 60 * 10;
+
+struct Args {
+    level: PathBuf,
+    waves: Option<PathBuf>,
+    script: Option<PathBuf>,
+    seed: u64,
+    runs: u64,
+    bot: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let usage = "Usage: td-sim <level.tmx> [--waves <waves.ron>] [--script <script.ron>] \
+                 [--seed <n>] [--runs <n>] [--bot]\n       td-sim validate <level.tmx>...";
+    let level = args.next().ok_or(usage)?;
+    let mut parsed = Args {
+        level: PathBuf::from(level),
+        waves: None,
+        script: None,
+        seed: 0,
+        runs: 1,
+        bot: false,
+    };
+    while let Some(arg) = args.next() {
+        if arg == "--bot" {
+            parsed.bot = true;
+            continue;
+        }
+        let value = args.next().ok_or(usage)?;
+        let number = || {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid value for {}: {}", arg, value))
+        };
+        match arg.as_str() {
+            "--waves" => parsed.waves = Some(PathBuf::from(&value)),
+            "--script" => parsed.script = Some(PathBuf::from(&value)),
+            "--seed" => parsed.seed = number()?,
+            "--runs" => parsed.runs = number()?,
+            _ => return Err(usage.to_string()),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Loads a level, listing everything which is wrong with it if it can't be.
+fn load(path: &Path) -> Result<Level, String> {
+    match load_level(path) {
+        Ok((_, level)) => Ok(level),
+        Err(LevelLoadError::Invalid(errors)) => {
+            let mut description = format!("{} can't be played:", path.display());
+            for error in errors {
+                description.push_str(&format!("\n  {}", error));
+            }
+            Err(description)
+        }
+        Err(e) => Err(format!("Couldn't load {}: {}", path.display(), e)),
+    }
+}
+
+/// Checks each of the levels, and fails if any of them can't be played.
+fn validate(paths: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut invalid = 0;
+    for path in paths.map(PathBuf::from) {
+        match load(&path) {
+            Ok(_) => println!("{}: ok", path.display()),
+            Err(e) => {
+                eprintln!("{}", e);
+                invalid += 1;
+            }
+        }
+    }
+    if invalid > 0 {
+        return Err(format!("{} levels can't be played", invalid));
+    }
+    Ok(())
+}
+
+fn load_script(path: &Path) -> Result<Vec<(u64, Command)>, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    ron::de::from_reader(std::io::BufReader::new(file)).map_err(|e| e.to_string())
+}
+
+/// Everything games of a level are played with, besides the seed.
+struct Game {
+    level: Level,
+    waves: Waves,
+    archetypes: Archetypes,
+    script: Vec<(u64, Command)>,
+}
+
+/// Plays a single game and prints what happened.
+fn run(game: &Game, seed: u64, bot: bool) -> bool {
+    let bot = if bot {
+        Some(Bot::new(&game.level))
+    } else {
+        None
+    };
+    let mut sim = Simulation::new(game.level.clone(), game.waves.clone(), seed)
+        .with_archetypes(game.archetypes.clone());
+    sim.play(game.script.clone());
+    let mut gold = vec![];
+    while !sim.is_won() && !sim.is_lost() && sim.tick() < MAX_TICKS {
+        if sim.tick() % GOLD_INTERVAL == 0 {
+            gold.push(sim.gold());
+        }
+        if let Some(bot) = &bot {
+            bot.act(&mut sim);
+        }
+        sim.step();
+    }
+
+    let outcome = if sim.is_won() {
+        "won"
+    } else if sim.is_lost() {
+        "lost"
+    } else {
+        "unfinished"
+    };
+    println!(
+        "seed {}: {} after {:.1}s with {} lives and {} gold",
+        seed,
+        outcome,
+        sim.tick() as f32 * DT,
+        sim.lives(),
+        sim.gold()
+    );
+    for wave in 0..sim.waves().len() {
+        println!(
+            "  wave {}: {} runners leaked",
+            wave + 1,
+            sim.stats().leaked(wave)
+        );
+    }
+    let gold: Vec<String> = gold.iter().map(|g| g.to_string()).collect();
+    println!(
+        "  gold every {}s: {}",
+        GOLD_INTERVAL as f32 * DT,
+        gold.join(" ")
+    );
+    // upgrades keep what the towers they replaced did
+    let sold = sim.stats().sold().iter().map(|t| (t, " (sold)"));
+    for (tower, note) in sim.towers().iter().map(|t| (t, "")).chain(sold) {
+        println!(
+            "  {:?} tower at ({}, {}): {:.0} damage, {} kills{}",
+            tower.kind(),
+            tower.pos().x,
+            tower.pos().y,
+            tower.damage_dealt(),
+            tower.kills(),
+            note
+        );
+    }
+    sim.is_won()
+}
+
+fn main() {
+    if let Err(e) = run_cli() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run_cli() -> Result<(), String> {
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("validate") {
+        return validate(args.skip(1));
+    }
+    let args = parse_args(args)?;
+    let level = load(&args.level)?;
+    let waves = match &args.waves {
+        Some(path) => {
+            let waves = Waves::load(path).map_err(|e| e.to_string())?;
+            let errors: Vec<String> = waves
+                .validate(&level)
+                .iter()
+                .map(|e| e.to_string())
+                .collect();
+            if !errors.is_empty() {
+                return Err(errors.join("\n"));
+            }
+            waves
+        }
+        None => Waves::for_level(&args.level, &level)
+            .map_err(|e| format!("Couldn't load the waves of {}: {}", args.level.display(), e))?,
+    };
+    let script = match &args.script {
+        Some(path) => load_script(path)?,
+        None => vec![],
+    };
+    let archetypes = Archetypes::for_level(&args.level).map_err(|e| {
+        format!(
+            "Couldn't load the runners of {}: {}",
+            args.level.display(),
+            e
+        )
+    })?;
+    let game = Game {
+        level,
+        waves,
+        archetypes,
+        script,
+    };
+
+    let mut won = 0;
+    for seed in args.seed..args.seed + args.runs {
+        if run(&game, seed, args.bot) {
+            won += 1;
+        }
+    }
+    if args.runs > 1 {
+        println!("won {} out of {} games", won, args.runs);
+    }
+    if args.bot && won < args.runs {
+        return Err(format!("The bot couldn't beat {}", args.level.display()));
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+//! Moves the camera over maps larger than the window: panning with the
+//! `pan-*` actions, the edges of the window or by dragging with `drag-camera`,
+//! and zooming with the `zoom-*` actions.
+
+use amethyst::core::{Time, Transform};
+use amethyst::derive::SystemDesc;
+use amethyst::ecs::{Entities, Entity, Join, Read, ReadExpect, System, SystemData, WriteStorage};
+use amethyst::input::{InputEvent, InputHandler, StringBindings};
+use amethyst::renderer::Camera;
+use amethyst::shrev::{EventChannel, ReaderId};
+use amethyst::window::ScreenDimensions;
+
+use crate::{render, sim::Simulation, GameState};
+
+/// How fast the camera pans, in pixels of the window per second.
+pub const PAN_SPEED: f32 = 400.0;
+/// How close to the edge of the window the mouse pans the camera, in pixels.
+pub const EDGE_SIZE: f32 = 8.0;
+/// How much a step of the mouse wheel or of a zoom key zooms.
+pub const ZOOM_STEP: f32 = 1.25;
+/// How many pixels of the window a pixel of the map takes at most.
+pub const MAX_ZOOM: f32 = 4.0;
+
+/// Pans and zooms the camera, keeping it over the map, and keeps its
+/// projection the size of the window.
+#[derive(SystemDesc)]
+#[system_desc(name(CameraSystemDesc))]
+pub struct CameraSystem {
+    #[system_desc(event_channel_reader)]
+    reader: ReaderId<InputEvent<StringBindings>>,
+    // the camera of the level, which is zoomed to fit the map when it changes
+    #[system_desc(skip)]
+    camera: Option<Entity>,
+    // how many pixels of the window a pixel of the map takes
+    #[system_desc(skip)]
+    zoom: f32,
+    // the size of the window and the zoom the projection was made for
+    #[system_desc(skip)]
+    projection: Option<(f32, f32, f32)>,
+    // where the mouse was last frame while dragging the camera
+    #[system_desc(skip)]
+    drag_from: Option<(f32, f32)>,
+}
+
+impl CameraSystem {
+    pub fn new(reader: ReaderId<InputEvent<StringBindings>>) -> Self {
+        Self {
+            reader,
+            camera: None,
+            zoom: 1.0,
+            projection: None,
+            drag_from: None,
+        }
+    }
+}
+
+impl<'s> System<'s> for CameraSystem {
+    type SystemData = (
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Read<'s, InputHandler<StringBindings>>,
+        ReadExpect<'s, ScreenDimensions>,
+        Read<'s, Time>,
+        Option<Read<'s, Simulation>>,
+        WriteStorage<'s, Camera>,
+        WriteStorage<'s, Transform>,
+        Read<'s, GameState>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            events,
+            input,
+            dim,
+            time,
+            sim,
+            mut cameras,
+            mut transforms,
+            state,
+            entities,
+        ): Self::SystemData,
+    ) {
+        // events are read every frame, so none are left over when a game starts
+        let zoom_steps: i32 = events
+            .read(&mut self.reader)
+            .map(|event| match event {
+                InputEvent::ActionPressed(action) | InputEvent::ActionWheelMoved(action) => {
+                    match action.as_str() {
+                        "zoom-in" => 1,
+                        "zoom-out" => -1,
+                        _ => 0,
+                    }
+                }
+                _ => 0,
+            })
+            .sum();
+        if *state != GameState::Game {
+            return;
+        }
+        let sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
+        let (entity, camera, trans) = match (&entities, &mut cameras, &mut transforms).join().next()
+        {
+            Some(camera) => camera,
+            None => return,
+        };
+        let level = sim.level();
+        let (width, height) = level.size();
+        let map_width = (width * level.tile_width()) as f32;
+        let map_height = (height * level.tile_height()) as f32;
+        let (screen_width, screen_height) = (dim.width(), dim.height());
+
+        // zoomed out as far as possible, the whole map is in the window
+        let min_zoom = (screen_width / map_width).min(screen_height / map_height);
+        let max_zoom = MAX_ZOOM.max(min_zoom);
+        if self.camera != Some(entity) {
+            self.camera = Some(entity);
+            self.zoom = min_zoom;
+            self.projection = None;
+        }
+        self.zoom = between(self.zoom * ZOOM_STEP.powi(zoom_steps), min_zoom, max_zoom);
+
+        let mut pan = (0.0, 0.0);
+        let actions = [
+            ("pan-left", -1.0, 0.0),
+            ("pan-right", 1.0, 0.0),
+            ("pan-up", 0.0, 1.0),
+            ("pan-down", 0.0, -1.0),
+        ];
+        for &(action, dx, dy) in actions.iter() {
+            if input.action_is_down(action).unwrap_or(false) {
+                pan.0 += dx * PAN_SPEED * time.delta_real_seconds();
+                pan.1 += dy * PAN_SPEED * time.delta_real_seconds();
+            }
+        }
+        // the window's y axis points down, ours points up
+        let mouse = input.mouse_position();
+        if let Some((x, y)) = mouse {
+            let step = PAN_SPEED * time.delta_real_seconds();
+            if x < EDGE_SIZE {
+                pan.0 -= step;
+            } else if x > screen_width - EDGE_SIZE {
+                pan.0 += step;
+            }
+            if y < EDGE_SIZE {
+                pan.1 += step;
+            } else if y > screen_height - EDGE_SIZE {
+                pan.1 -= step;
+            }
+        }
+        // dragging moves the map along with the mouse
+        if input.action_is_down("drag-camera").unwrap_or(false) {
+            if let (Some((x, y)), Some((from_x, from_y))) = (mouse, self.drag_from) {
+                pan.0 -= x - from_x;
+                pan.1 += y - from_y;
+            }
+            self.drag_from = mouse;
+        } else {
+            self.drag_from = None;
+        }
+
+        // the camera can't show what's beyond the map, unless all of it fits
+        let (view_width, view_height) = (screen_width / self.zoom, screen_height / self.zoom);
+        let translation = trans.translation();
+        let x = center(translation.x + pan.0 / self.zoom, view_width, map_width);
+        let y = center(translation.y + pan.1 / self.zoom, view_height, map_height);
+        trans.set_translation_xyz(x, y, render::CAMERA);
+
+        // the window was resized, or the camera zoomed
+        let projection = (screen_width, screen_height, self.zoom);
+        if self.projection != Some(projection) {
+            self.projection = Some(projection);
+            *camera = Camera::standard_2d(view_width, view_height);
+        }
+    }
+}
+
+/// Where the center of a view of size `view` can be, on a map of size `map`.
+fn center(center: f32, view: f32, map: f32) -> f32 {
+    if view >= map {
+        map / 2.0
+    } else {
+        between(center, view / 2.0, map - view / 2.0)
+    }
+}
+
+fn between(value: f32, min: f32, max: f32) -> f32 {
+    if value < min {
+        min
+    } else if value > max {
+        max
+    } else {
+        value
+    }
+}
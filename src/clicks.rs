@@ -0,0 +1,133 @@
+//! Turns the mouse into events sent once per click or move, so a click held
+//! for several frames is only handled once.
+
+use amethyst::core::{
+    math::{Point3, Vector2},
+    Transform,
+};
+use amethyst::derive::SystemDesc;
+use amethyst::ecs::{Join, Read, ReadExpect, ReadStorage, System, SystemData, Write};
+use amethyst::input::{InputEvent, InputHandler, StringBindings};
+use amethyst::renderer::Camera;
+use amethyst::shrev::{EventChannel, ReaderId};
+use amethyst::window::ScreenDimensions;
+
+/// How far the mouse has to move, in world units, before a press becomes a
+/// drag, which isn't a click.
+pub const DRAG_DISTANCE: f32 = 4.0;
+
+/// What the mouse did, with positions in world coordinates.
+#[derive(Debug, Clone)]
+pub enum ClickEvent {
+    /// `select` went up without the mouse being dragged.
+    Clicked(Transform),
+    /// The `cancel` action went down, wherever the mouse is.
+    Cancelled,
+    /// The mouse moved, or the world moved under it.
+    Hovered(Transform),
+}
+
+/// Sends a `ClickEvent` for what the mouse did since last frame.
+#[derive(SystemDesc)]
+#[system_desc(name(ClickSystemDesc))]
+pub struct ClickSystem {
+    #[system_desc(event_channel_reader)]
+    reader: ReaderId<InputEvent<StringBindings>>,
+    // where the mouse was last frame
+    #[system_desc(skip)]
+    last: Option<Transform>,
+    // where `select` went down, and whether it is being dragged since
+    #[system_desc(skip)]
+    pressed: Option<(Transform, bool)>,
+}
+
+impl ClickSystem {
+    pub fn new(reader: ReaderId<InputEvent<StringBindings>>) -> Self {
+        Self {
+            reader,
+            last: None,
+            pressed: None,
+        }
+    }
+}
+
+impl<'s> System<'s> for ClickSystem {
+    type SystemData = (
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Write<'s, EventChannel<ClickEvent>>,
+        Read<'s, InputHandler<StringBindings>>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (input_events, mut clicks, input, dim, cameras, transforms): Self::SystemData,
+    ) {
+        let events: Vec<_> = input_events.read(&mut self.reader).collect();
+        // there is nothing to click on until a level made a camera
+        let pos = match (&cameras, &transforms).join().next() {
+            Some((camera, camera_trans)) => world_position(&input, &dim, camera, camera_trans),
+            None => None,
+        };
+        let pos = match pos {
+            Some(pos) => pos,
+            None => {
+                self.last = None;
+                self.pressed = None;
+                return;
+            }
+        };
+
+        let moved = self
+            .last
+            .as_ref()
+            .map_or(true, |last| last.translation() != pos.translation());
+        if moved {
+            clicks.single_write(ClickEvent::Hovered(pos.clone()));
+            if let Some((from, dragging)) = self.pressed.as_mut() {
+                let (a, b) = (from.translation(), pos.translation());
+                if (a - b).xy().norm() > DRAG_DISTANCE {
+                    *dragging = true;
+                }
+            }
+        }
+        self.last = Some(pos.clone());
+
+        for event in events {
+            match event {
+                InputEvent::ActionPressed(action) if action == "select" => {
+                    self.pressed = Some((pos.clone(), false));
+                }
+                InputEvent::ActionReleased(action) if action == "select" => {
+                    // not a click if it was dragged, or pressed before there was
+                    // a camera
+                    if let Some((_, false)) = self.pressed.take() {
+                        clicks.single_write(ClickEvent::Clicked(pos.clone()));
+                    }
+                }
+                InputEvent::ActionPressed(action) if action == "cancel" => {
+                    clicks.single_write(ClickEvent::Cancelled);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Gets the mouse position in terms of world coordinates.
+fn world_position(
+    input: &InputHandler<StringBindings>,
+    dim: &ScreenDimensions,
+    camera: &Camera,
+    camera_trans: &Transform,
+) -> Option<Transform> {
+    let m_pos = input.mouse_position()?;
+    let screen_dimensions = Vector2::new(dim.width(), dim.height());
+    let mouse_pos = Point3::new(m_pos.0, m_pos.1, 0.0);
+    let mouse_coords = camera.screen_to_world_point(mouse_pos, screen_dimensions, camera_trans);
+    let mut mouse_trans = Transform::default();
+    mouse_trans.set_translation_xyz(mouse_coords.coords[0], mouse_coords.coords[1], 1.0);
+    Some(mouse_trans)
+}
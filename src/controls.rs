@@ -0,0 +1,320 @@
+//! The actions keys and mouse buttons can be bound to, and the screen which
+//! rebinds them. Bindings are read from and saved to `config/bindings.ron`.
+
+use amethyst::{
+    assets::Loader,
+    ecs::prelude::Entity,
+    input::{is_close_requested, Bindings, Button, InputEvent, InputHandler, StringBindings},
+    prelude::*,
+    ui::*,
+    utils::application_root_dir,
+    winit::VirtualKeyCode,
+};
+use ron::ser::PrettyConfig;
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::MainMenuState;
+
+/// Every action of the game, and what it does.
+pub const ACTIONS: [(&str, &str); 17] = [
+    ("select", "Select a build point, tower or menu option"),
+    ("cancel", "Close the build menu"),
+    ("build-tower-1", "Build the first tower of the menu"),
+    ("build-tower-2", "Build the second tower of the menu"),
+    ("upgrade", "Upgrade the selected tower"),
+    ("sell", "Sell the selected tower"),
+    (
+        "retarget",
+        "Change which runner the selected tower shoots at",
+    ),
+    ("pause", "Pause or resume the game"),
+    ("speed-up", "Play faster, or back at 1x after 3x"),
+    ("next-wave", "Call the next wave early"),
+    ("pan-left", "Move the camera left"),
+    ("pan-right", "Move the camera right"),
+    ("pan-up", "Move the camera up"),
+    ("pan-down", "Move the camera down"),
+    ("drag-camera", "Move the camera by dragging the mouse"),
+    ("zoom-in", "Zoom in"),
+    ("zoom-out", "Zoom out"),
+];
+
+/// The actions building each of `TowerKind::buildable()` on the selected
+/// build point.
+pub const BUILD_ACTIONS: [&str; 2] = ["build-tower-1", "build-tower-2"];
+
+/// Where the bindings are kept.
+pub fn bindings_path() -> amethyst::Result<PathBuf> {
+    Ok(application_root_dir()?.join("config").join("bindings.ron"))
+}
+
+pub fn save_bindings(bindings: &Bindings<StringBindings>, path: &Path) -> io::Result<()> {
+    let ron = ron::ser::to_string_pretty(bindings, PrettyConfig::default())
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    std::fs::write(path, ron)
+}
+
+/// The actions which always keep a binding, as the game can't be played
+/// without them.
+pub const REQUIRED_ACTIONS: [&str; 2] = ["select", "cancel"];
+
+/// Binds `action` to `button` alone, taking the button away from the
+/// actions it was bound to before. Nothing changes if that would leave one
+/// of `REQUIRED_ACTIONS` unbound, and that action is returned.
+pub fn rebind(
+    bindings: &mut Bindings<StringBindings>,
+    action: &str,
+    button: Button,
+) -> Result<(), &'static str> {
+    // an action which has no binding yet can't lose it, and can still be
+    // given one from the controls screen
+    let unbound = REQUIRED_ACTIONS.iter().find(|&&required| {
+        let mut combos = bindings.action_bindings(required).peekable();
+        required != action && combos.peek().is_some() && combos.all(|combo| combo.contains(&button))
+    });
+    if let Some(required) = unbound {
+        return Err(*required);
+    }
+    let actions: Vec<String> = bindings.actions().cloned().collect();
+    for other in actions {
+        let combos: Vec<Vec<Button>> = bindings
+            .action_bindings(&other)
+            .map(|combo| combo.to_vec())
+            .collect();
+        for combo in combos {
+            if other == action || combo.contains(&button) {
+                bindings.remove_action_binding(&other, &combo);
+            }
+        }
+    }
+    // nothing uses the button anymore, so this can't fail
+    bindings
+        .insert_action_binding(action.to_string(), vec![button])
+        .unwrap();
+    Ok(())
+}
+
+/// The buttons bound to `action`, e.g. "S" or "Mouse Left".
+pub fn describe(bindings: &Bindings<StringBindings>, action: &str) -> String {
+    let combos: Vec<String> = bindings
+        .action_bindings(action)
+        .map(|combo| {
+            combo
+                .iter()
+                .map(|button| match button {
+                    Button::Key(key) => format!("{:?}", key),
+                    Button::Mouse(button) => format!("Mouse {:?}", button),
+                    Button::MouseWheel(direction) => format!("Mouse wheel {:?}", direction),
+                    other => format!("{:?}", other),
+                })
+                .collect::<Vec<_>>()
+                .join("+")
+        })
+        .collect();
+    if combos.is_empty() {
+        "nothing".to_string()
+    } else {
+        combos.join(" or ")
+    }
+}
+
+/// Lists the actions with what they're bound to. Clicking an action binds it
+/// to the next key or mouse button pressed.
+pub struct ControlsState {
+    // the seed the main menu plays levels with
+    seed: Option<u64>,
+    // the button of each action, and its text
+    rows: Vec<(&'static str, Entity, Entity)>,
+    back: Option<Entity>,
+    // tells whether the bindings could be saved
+    status: Option<Entity>,
+    // the action which gets the next key or mouse button pressed
+    waiting: Option<&'static str>,
+    // the button under the mouse, whose clicks don't bind anything
+    hovered: Option<Entity>,
+}
+
+impl ControlsState {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            rows: vec![],
+            back: None,
+            status: None,
+            waiting: None,
+            hovered: None,
+        }
+    }
+
+    /// Shows what each action is bound to.
+    fn refresh(&self, world: &mut World) {
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        let mut texts = world.write_storage::<UiText>();
+        for &(action, _, text) in self.rows.iter() {
+            let description = ACTIONS.iter().find(|(a, _)| *a == action).unwrap().1;
+            texts.get_mut(text).unwrap().text = if self.waiting == Some(action) {
+                format!("{}: press a key (Escape to cancel)", description)
+            } else {
+                format!("{}: {}", description, describe(&input.bindings, action))
+            };
+        }
+    }
+
+    /// Binds the action waiting for a button to `button`, and saves the
+    /// bindings for the next games.
+    fn bind(&mut self, world: &mut World, button: Button) {
+        let action = match self.waiting.take() {
+            Some(action) => action,
+            None => return,
+        };
+        let saved = {
+            let mut input = world.write_resource::<InputHandler<StringBindings>>();
+            match rebind(&mut input.bindings, action, button) {
+                Ok(()) => bindings_path()
+                    .map_err(|e| e.to_string())
+                    .and_then(|path| {
+                        save_bindings(&input.bindings, &path).map_err(|e| e.to_string())
+                    })
+                    .map_err(|e| format!("Couldn't save the bindings: {}", e)),
+                Err(required) => Err(format!(
+                    "{} can't be taken away from {}, its only binding",
+                    describe(&input.bindings, required),
+                    required
+                )),
+            }
+        };
+        if let Some(status) = self.status {
+            world
+                .write_storage::<UiText>()
+                .get_mut(status)
+                .unwrap()
+                .text = saved.err().unwrap_or_default();
+        }
+        self.refresh(world);
+    }
+
+    fn back_to_menu(&mut self, world: &mut World) -> SimpleTrans {
+        let rows = self.rows.drain(..).map(|(_, button, _)| button);
+        for e in rows.chain(self.back.take()).chain(self.status.take()) {
+            world.delete_entity(e).unwrap();
+        }
+        self.hovered = None;
+        Trans::Switch(Box::new(MainMenuState::new(self.seed)))
+    }
+}
+
+impl SimpleState for ControlsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        for (i, &(action, _)) in ACTIONS.iter().enumerate() {
+            let (_, button) = UiButtonBuilder::<(), u32>::new("")
+                .with_font_size(18.0)
+                .with_position(32.0, -32.0 - i as f32 * 28.0)
+                .with_size(600.0, 24.0)
+                .with_image(UiImage::SolidColor([0.9, 0.9, 0.9, 1.]))
+                .with_anchor(Anchor::TopLeft)
+                .build_from_world(&world);
+            self.rows
+                .push((action, button.image_entity, button.text_entity));
+        }
+        let (_, back) = UiButtonBuilder::<(), u32>::new("Back")
+            .with_font_size(24.0)
+            .with_position(32.0, -48.0 - ACTIONS.len() as f32 * 28.0)
+            .with_size(100.0, 32.0)
+            .with_image(UiImage::SolidColor([0.9, 0.9, 0.9, 1.]))
+            .with_anchor(Anchor::TopLeft)
+            .build_from_world(&world);
+        self.back = Some(back.image_entity);
+        let font = world.read_resource::<Loader>().load(
+            "font/square.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let status = world
+            .create_entity()
+            .with(UiTransform::new(
+                "controls-status".to_string(),
+                Anchor::BottomLeft,
+                Anchor::BottomLeft,
+                32.,
+                32.,
+                0.0,
+                600.,
+                24.,
+            ))
+            .with(UiText::new(
+                font,
+                String::new(),
+                [1., 0.4, 0.4, 1.],
+                18.,
+                LineMode::Single,
+                Anchor::MiddleLeft,
+            ))
+            .build();
+        self.status = Some(status);
+        self.refresh(world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match &event {
+            StateEvent::Window(event) if is_close_requested(event) => Trans::Quit,
+            StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) => {
+                if *key_code != VirtualKeyCode::Escape {
+                    self.bind(data.world, Button::Key(*key_code));
+                    Trans::None
+                } else if self.waiting.take().is_some() {
+                    self.refresh(data.world);
+                    Trans::None
+                } else {
+                    self.back_to_menu(data.world)
+                }
+            }
+            // clicking a row or Back picks it rather than binding the mouse
+            StateEvent::Input(InputEvent::MouseButtonPressed(button)) => {
+                if self.hovered.is_none() {
+                    self.bind(data.world, Button::Mouse(*button));
+                }
+                Trans::None
+            }
+            StateEvent::Ui(UiEvent {
+                event_type: UiEventType::HoverStart,
+                target,
+            }) => {
+                self.hovered = Some(*target);
+                Trans::None
+            }
+            StateEvent::Ui(UiEvent {
+                event_type: UiEventType::HoverStop,
+                target,
+            }) => {
+                if self.hovered == Some(*target) {
+                    self.hovered = None;
+                }
+                Trans::None
+            }
+            StateEvent::Ui(UiEvent {
+                event_type: UiEventType::Click,
+                target,
+            }) => {
+                if Some(*target) == self.back {
+                    return self.back_to_menu(data.world);
+                }
+                if let Some(&(action, _, _)) = self.rows.iter().find(|(_, b, _)| b == target) {
+                    self.waiting = Some(action);
+                    self.refresh(data.world);
+                }
+                Trans::None
+            }
+            _ => Trans::None,
+        }
+    }
+}
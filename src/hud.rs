@@ -0,0 +1,154 @@
+//! The bar at the top of the window while playing, made from the
+//! `ui/hud.ron` prefab. Gameplay systems send it `HudEvent`s instead of
+//! editing its texts themselves.
+
+use amethyst::core::{HiddenPropagate, Time};
+use amethyst::derive::SystemDesc;
+use amethyst::ecs::{Read, System, SystemData, WriteStorage};
+use amethyst::shrev::{EventChannel, ReaderId};
+use amethyst::ui::{UiFinder, UiText};
+
+use std::collections::HashMap;
+
+use crate::{
+    sim::{Replay, Simulation},
+    GameState,
+};
+
+/// The prefab the HUD is made from.
+pub const PREFAB: &str = "ui/hud.ron";
+/// The id of the button calling the next wave early.
+pub const CALL_EARLY: &str = "hud-call-early";
+const MESSAGE: &str = "hud-message";
+/// How long messages take to fade away, in seconds.
+const MESSAGE_SECS: f32 = 3.0;
+
+/// Something the HUD has to show.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HudEvent {
+    Gold(usize),
+    Lives(usize),
+    Score(usize),
+    /// The wave being spawned, counting from 0, and how many there are.
+    Wave {
+        current: usize,
+        count: usize,
+    },
+    /// How many seconds are left before the next wave, rounded up, or `None`
+    /// once it started.
+    NextWave(Option<u32>),
+    /// An error or some news, which fades away.
+    Message(String),
+}
+
+impl HudEvent {
+    /// Everything the HUD shows about a game.
+    pub fn status(sim: &Simulation) -> Vec<HudEvent> {
+        let waves = sim.waves();
+        vec![
+            HudEvent::Gold(sim.gold()),
+            HudEvent::Lives(sim.lives()),
+            HudEvent::Score(sim.score()),
+            HudEvent::Wave {
+                current: waves.current(),
+                count: waves.len(),
+            },
+            HudEvent::NextWave(waves.time_until_next().map(|secs| secs.ceil() as u32)),
+        ]
+    }
+
+    /// The widget of the prefab showing the event, and its text.
+    fn label(&self) -> (&'static str, String) {
+        match self {
+            HudEvent::Gold(gold) => ("hud-gold", format!("{} gold", gold)),
+            HudEvent::Lives(lives) => ("hud-lives", format!("{} lives", lives)),
+            HudEvent::Score(score) => ("hud-score", format!("Score {}", score)),
+            // the last wave stays shown once all of them spawned
+            HudEvent::Wave { current, count } => (
+                "hud-wave",
+                format!("Wave {}/{}", (current + 1).min(*count), count),
+            ),
+            HudEvent::NextWave(Some(secs)) => ("hud-next-wave", format!("Next wave in {}s", secs)),
+            HudEvent::NextWave(None) => ("hud-next-wave", String::new()),
+            HudEvent::Message(text) => (MESSAGE, text.clone()),
+        }
+    }
+}
+
+/// Shows what `HudEvent`s tell in the widgets of the prefab.
+#[derive(SystemDesc)]
+#[system_desc(name(HudSystemDesc))]
+pub struct HudSystem {
+    #[system_desc(event_channel_reader)]
+    reader: ReaderId<HudEvent>,
+    // the text of each widget, kept until the prefab is loaded
+    #[system_desc(skip)]
+    texts: HashMap<&'static str, String>,
+    // whether the next wave is counting down, and can be called early
+    #[system_desc(skip)]
+    counting_down: bool,
+    #[system_desc(skip)]
+    message_timer: f32,
+}
+
+impl HudSystem {
+    pub fn new(reader: ReaderId<HudEvent>) -> Self {
+        Self {
+            reader,
+            texts: Default::default(),
+            counting_down: false,
+            message_timer: 0.0,
+        }
+    }
+}
+
+impl<'s> System<'s> for HudSystem {
+    type SystemData = (
+        Read<'s, EventChannel<HudEvent>>,
+        UiFinder<'s>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, HiddenPropagate>,
+        Option<Read<'s, Replay>>,
+        Read<'s, Time>,
+        Read<'s, GameState>,
+    );
+
+    fn run(
+        &mut self,
+        (events, finder, mut texts, mut hidden, replay, time, state): Self::SystemData,
+    ) {
+        for event in events.read(&mut self.reader) {
+            match event {
+                HudEvent::NextWave(secs) => self.counting_down = secs.is_some(),
+                HudEvent::Message(_) => self.message_timer = MESSAGE_SECS,
+                _ => {}
+            }
+            let (id, text) = event.label();
+            self.texts.insert(id, text);
+        }
+        if *state != GameState::Game {
+            return;
+        }
+
+        for (id, text) in self.texts.iter() {
+            let widget = finder.find(id).and_then(|entity| texts.get_mut(entity));
+            if let Some(widget) = widget {
+                if widget.text != *text {
+                    widget.text = text.clone();
+                }
+            }
+        }
+        self.message_timer = (self.message_timer - time.delta_seconds()).max(0.0);
+        if let Some(message) = finder.find(MESSAGE).and_then(|e| texts.get_mut(e)) {
+            message.color[3] = self.message_timer / MESSAGE_SECS;
+        }
+        // nobody gets to play during a replay
+        if let Some(button) = finder.find(CALL_EARLY) {
+            if self.counting_down && replay.is_none() {
+                hidden.remove(button);
+            } else if !hidden.contains(button) {
+                hidden.insert(button, HiddenPropagate::new()).unwrap();
+            }
+        }
+    }
+}
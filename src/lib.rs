@@ -1,37 +1,24 @@
 use amethyst::core::transform::Transform;
 
+pub mod animation;
+pub mod camera;
+pub mod clicks;
+pub mod controls;
+pub mod hud;
 pub mod map;
+pub mod render;
 pub mod runner;
+pub mod sim;
 pub mod states;
 pub mod tower;
+pub use sim::Coord;
 pub use states::{GameState, MainMenuState, TowerDefState};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Coord {
-    pub x: usize,
-    pub y: usize,
-}
-
 impl Coord {
-    pub fn new(x: usize, y: usize) -> Self {
-        Self { x, y }
-    }
-
     pub fn to_trans(&self, tile_w: usize, tile_h: usize) -> Transform {
         let mut pos = Transform::default();
-        let offset_x = (tile_w / 2) as f32;
-        let offset_y = (tile_h / 2) as f32;
-        pos.set_translation_xyz(
-            (self.x * tile_w) as f32 + offset_x,
-            (self.y * tile_h) as f32 + offset_y,
-            0.0,
-        );
+        let center = self.center(tile_w, tile_h);
+        pos.set_translation_xyz(center.x, center.y, 0.0);
         pos
     }
-
-    pub fn distance(&self, other: &Coord) -> f32 {
-        (((self.x as isize - other.x as isize).pow(2) + (self.y as isize - other.y as isize).pow(2))
-            as f32)
-            .sqrt()
-    }
 }
@@ -1,4 +1,6 @@
-use tower_def::MainMenuState;
+use tower_def::{sim::Replay, MainMenuState, TowerDefState};
+
+use std::path::PathBuf;
 
 use amethyst::{
     core::transform::TransformBundle,
@@ -16,28 +18,76 @@ use amethyst::{
 fn main() -> amethyst::Result<()> {
     amethyst::start_logger(Default::default());
 
+    let args = parse_args(std::env::args().skip(1))?;
+
     let app_root = application_root_dir()?;
 
     let assets_dir = app_root.join("assets");
     let config_dir = app_root.join("config");
     let display_config_path = config_dir.join("display.ron");
 
-    let input_bundle = InputBundle::<StringBindings>::new();
+    let input_bundle = InputBundle::<StringBindings>::new()
+        .with_bindings_from_file(tower_def::controls::bindings_path()?)?;
 
     let game_data = GameDataBuilder::default()
         .with_bundle(TransformBundle::new())?
         .with_bundle(input_bundle)?
         .with_bundle(UiBundle::<StringBindings>::new())?
-        .with(tower_def::runner::RunnerSystem, "runner_sytem", &[])
-        .with(tower_def::runner::SpawnSystem::new(), "spawn_system", &[])
-        .with(tower_def::tower::TowerSystem, "tower_sytem", &[])
-        .with(tower_def::tower::MissleSystem, "missle_sytem", &[])
-        .with(tower_def::map::FontSystem::new(), "font_system", &[])
         .with(
-            tower_def::tower::BuildPointSystem::new(),
-            "build_point_system",
+            tower_def::map::SimulationSystem::new(),
+            "simulation_system",
+            &[],
+        )
+        .with(
+            tower_def::runner::RunnerSystem::new(),
+            "runner_sytem",
+            &["simulation_system"],
+        )
+        .with(
+            tower_def::runner::BossSystem,
+            "boss_system",
+            &["simulation_system"],
+        )
+        .with(
+            tower_def::runner::HealthBarSystem::new(),
+            "health_bar_system",
+            &["simulation_system"],
+        )
+        .with(
+            tower_def::tower::TowerSystem::new(),
+            "tower_sytem",
+            &["simulation_system"],
+        )
+        .with(
+            tower_def::tower::MissleSystem::new(),
+            "missle_sytem",
+            &["simulation_system"],
+        )
+        .with(
+            tower_def::animation::AnimationSystem,
+            "animation_system",
+            &["runner_sytem", "tower_sytem", "missle_sytem"],
+        )
+        .with_system_desc(
+            tower_def::camera::CameraSystemDesc::default(),
+            "camera_system",
             &["input_system"],
         )
+        .with_system_desc(
+            tower_def::clicks::ClickSystemDesc::default(),
+            "click_system",
+            &["input_system", "camera_system"],
+        )
+        .with_system_desc(
+            tower_def::tower::BuildPointSystemDesc::default(),
+            "build_point_system",
+            &["click_system", "simulation_system"],
+        )
+        .with_system_desc(
+            tower_def::hud::HudSystemDesc::default(),
+            "hud_system",
+            &["simulation_system", "build_point_system"],
+        )
         .with_bundle(
             RenderingBundle::<DefaultBackend>::new()
                 .with_plugin(
@@ -48,8 +98,51 @@ fn main() -> amethyst::Result<()> {
                 .with_plugin(RenderUi::default()),
         )?;
 
-    let mut game = Application::new(assets_dir, MainMenuState::new(), game_data)?;
+    let mut game = if let Some(path) = args.replay {
+        let replay = Replay::load(&path).map_err(|e| {
+            amethyst::Error::from_string(format!("Couldn't load {}: {}", path.display(), e))
+        })?;
+        let level = replay.level().display().to_string();
+        let state = TowerDefState::replay(replay)
+            .map_err(|e| amethyst::Error::from_string(format!("Couldn't load {}: {}", level, e)))?;
+        Application::new(assets_dir, state, game_data)?
+    } else {
+        Application::new(assets_dir, MainMenuState::new(args.seed), game_data)?
+    };
     game.run();
 
     Ok(())
 }
+
+/// What can be passed to the game on the command line.
+#[derive(Default)]
+struct Args {
+    /// `--seed <n>` makes levels play out the same way every time.
+    seed: Option<u64>,
+    /// `--replay <file>` plays back a recorded game.
+    replay: Option<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> amethyst::Result<Args> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| amethyst::Error::from_string(format!("{} needs a value", arg)))?;
+        match arg.as_str() {
+            "--seed" => {
+                parsed.seed = Some(value.parse().map_err(|_| {
+                    amethyst::Error::from_string(format!("Invalid seed: {}", value))
+                })?)
+            }
+            "--replay" => parsed.replay = Some(PathBuf::from(value)),
+            _ => {
+                return Err(amethyst::Error::from_string(format!(
+                    "Unknown argument: {}",
+                    arg
+                )))
+            }
+        }
+    }
+    Ok(parsed)
+}
@@ -1,14 +1,55 @@
 mod components;
 mod systems;
 pub use components::Map;
-pub use systems::FontSystem;
+pub use systems::SimulationSystem;
 
 use amethyst::{
     assets::{AssetStorage, Handle, Loader},
     prelude::*,
-    renderer::{ImageFormat, Sprite, SpriteSheet, Texture},
+    renderer::{
+        loaders::load_from_srgba, palette::Srgba, types::TextureData, ImageFormat, Sprite,
+        SpriteRender, SpriteSheet, Texture,
+    },
 };
 
+use crate::{animation::Animation, sim::tileset_tile};
+
+/// Creates the sprite sheet of each tileset of `map`, in the same order.
+pub fn create_tileset_sprite_sheets(
+    map: &tiled::Map,
+    world: &mut World,
+) -> Vec<Handle<SpriteSheet>> {
+    // maps are validated to have a single image per tileset
+    map.tilesets
+        .iter()
+        .map(|tile_set| create_sprite_sheets(tile_set, world).remove(0))
+        .collect()
+}
+
+/// The sprite of the tile with the given gid, taken from the sprite sheet of
+/// the tileset it belongs to.
+pub fn tile_sprite(
+    map: &tiled::Map,
+    sprite_sheets: &[Handle<SpriteSheet>],
+    gid: u32,
+) -> Option<SpriteRender> {
+    tileset_tile(map, gid).map(|(tileset, id)| SpriteRender {
+        sprite_sheet: sprite_sheets[tileset].clone(),
+        sprite_number: id as usize,
+    })
+}
+
+/// The animation Tiled has for the tile with the given gid, if it has one.
+pub fn tile_animation(map: &tiled::Map, gid: u32) -> Option<Animation> {
+    let (tileset, id) = tileset_tile(map, gid)?;
+    map.tilesets[tileset]
+        .tiles
+        .iter()
+        .find(|tile| tile.id == id)
+        .and_then(|tile| tile.animation.as_deref())
+        .map(Animation::from_tiled)
+}
+
 pub fn create_sprite_sheets(
     tile_set: &tiled::Tileset,
     world: &mut World,
@@ -53,3 +94,23 @@ pub fn create_sprite_sheets(
     }
     handles
 }
+
+/// Creates a sprite sheet holding a single 1x1 sprite of the given colour,
+/// which can be scaled to draw rectangles (e.g. health bars).
+pub fn create_color_sprite_sheet(
+    color: [f32; 4],
+    loader: &Loader,
+    texture_storage: &AssetStorage<Texture>,
+    sprite_sheet_storage: &AssetStorage<SpriteSheet>,
+) -> Handle<SpriteSheet> {
+    let texture_data: TextureData =
+        load_from_srgba(Srgba::new(color[0], color[1], color[2], color[3])).into();
+    let texture = loader.load_from_data(texture_data, (), texture_storage);
+    let sprite_sheet = SpriteSheet {
+        texture,
+        sprites: vec![Sprite::from_pixel_values(
+            1, 1, 1, 1, 0, 0, [0.0; 2], false, false,
+        )],
+    };
+    loader.load_from_data(sprite_sheet, (), sprite_sheet_storage)
+}
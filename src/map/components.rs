@@ -1,32 +1,34 @@
 use amethyst::ecs::prelude::{Component, DenseVecStorage, Entity};
 use amethyst::{assets::Handle, renderer::SpriteSheet};
 
-use crate::Coord;
+use crate::{animation::Animation, map, runner::BossBar};
+
+/// How long tiles without an animation are shown when they're played as one.
+const FRAME_SECS: f32 = 0.3;
 
 pub struct Map {
     tiled_map: tiled::Map,
-    road: Vec<Vec<Coord>>,
-    sprite_sheet_handle: Handle<SpriteSheet>,
-    gold: usize,
-    gold_text: Entity,
-    error_text: Entity,
+    // one for each tileset of the map
+    sprite_sheets: Vec<Handle<SpriteSheet>>,
+    tooltip: Entity,
+    tower_info: Entity,
+    boss_bar: BossBar,
 }
 
 impl Map {
     pub fn new(
         tiled_map: tiled::Map,
-        road: Vec<Vec<Coord>>,
-        sprite_sheet_handle: Handle<SpriteSheet>,
-        gold_text: Entity,
-        error_text: Entity,
+        sprite_sheets: Vec<Handle<SpriteSheet>>,
+        tooltip: Entity,
+        tower_info: Entity,
+        boss_bar: BossBar,
     ) -> Self {
         Self {
             tiled_map,
-            road,
-            sprite_sheet_handle,
-            gold: 100,
-            gold_text,
-            error_text,
+            sprite_sheets,
+            tooltip,
+            tower_info,
+            boss_bar,
         }
     }
 
@@ -38,32 +40,62 @@ impl Map {
         self.tiled_map.tile_height
     }
 
-    pub fn road(&self) -> &Vec<Vec<Coord>> {
-        &self.road
+    /// The sprite sheet of the first tileset, which has the sprites of
+    /// runners, towers and missles.
+    pub fn sprite_sheet_handle(&self) -> Handle<SpriteSheet> {
+        self.sprite_sheets[0].clone()
     }
 
-    pub fn sprite_sheet_handle(&self) -> Handle<SpriteSheet> {
-        self.sprite_sheet_handle.clone()
+    /// The looping animation of a sprite of the first tileset, if its tile
+    /// has one.
+    pub fn animation(&self, sprite: usize) -> Option<Animation> {
+        let gid = self.tiled_map.tilesets[0].first_gid + sprite as u32;
+        map::tile_animation(&self.tiled_map, gid)
+    }
+
+    /// What's played when a tower with this sprite fires, taken from the tile
+    /// its `attack` property points to.
+    pub fn attack_animation(&self, sprite: usize) -> Option<Animation> {
+        self.linked_animation(sprite, "attack")
+    }
+
+    /// What's played when a runner with this sprite dies, taken from the tile
+    /// its `death` property points to.
+    pub fn death_animation(&self, sprite: usize) -> Option<Animation> {
+        self.linked_animation(sprite, "death")
     }
 
-    pub fn gold(&self) -> usize {
-        self.gold
+    fn linked_animation(&self, sprite: usize, property: &str) -> Option<Animation> {
+        let tile = self.tiled_map.tilesets[0]
+            .tiles
+            .iter()
+            .find(|tile| tile.id == sprite as u32)?;
+        let linked = match tile.properties.get(property) {
+            Some(tiled::PropertyValue::IntValue(id)) => *id as usize,
+            _ => return None,
+        };
+        Some(match self.animation(linked) {
+            Some(animation) => animation.played_once(),
+            None => Animation::once(vec![(linked, FRAME_SECS)]),
+        })
     }
 
-    pub fn add_gold(&mut self, gold: usize) {
-        self.gold += gold;
+    pub fn sprite_sheets(&self) -> &[Handle<SpriteSheet>] {
+        &self.sprite_sheets
     }
 
-    pub fn remove_gold(&mut self, gold: usize) {
-        self.gold -= gold;
+    /// The text explaining what's under the mouse, e.g. in the build menu.
+    pub fn tooltip(&self) -> Entity {
+        self.tooltip
     }
 
-    pub fn gold_text(&self) -> Entity {
-        self.gold_text.clone()
+    /// The panel describing the selected tower.
+    pub fn tower_info(&self) -> Entity {
+        self.tower_info
     }
 
-    pub fn error_text(&self) -> Entity {
-        self.error_text.clone()
+    pub fn boss_bar(&self) -> &BossBar {
+        &self.boss_bar
     }
 }
 
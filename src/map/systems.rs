@@ -1,42 +1,96 @@
 use amethyst::core::timing::Time;
 use amethyst::derive::SystemDesc;
-use amethyst::ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage};
-use amethyst::ui::UiText;
+use amethyst::ecs::{Read, System, SystemData, Write};
+use amethyst::shrev::EventChannel;
 
-use crate::{GameState, map::Map};
+use crate::{
+    hud::HudEvent,
+    sim::{Replay, Simulation, DT},
+    GameState,
+};
 
+/// Steps the simulation at a fixed rate, however long frames take, and tells
+/// the HUD what changed.
 #[derive(SystemDesc)]
-pub struct FontSystem {
-    font_timer: f32,
+pub struct SimulationSystem {
+    // time which passed but wasn't simulated yet
+    accumulator: f32,
+    // what the HUD was last told
+    hud: Vec<HudEvent>,
+    // whether the end of the replay being played back was reported
+    replay_over: bool,
 }
 
-impl FontSystem {
+impl SimulationSystem {
     pub fn new() -> Self {
-        Self { font_timer: 0.0 }
+        Self {
+            accumulator: 0.0,
+            hud: vec![],
+            replay_over: false,
+        }
     }
 }
 
-impl<'s> System<'s> for FontSystem {
+impl<'s> System<'s> for SimulationSystem {
     type SystemData = (
-        ReadStorage<'s, Map>,
+        Option<Write<'s, Simulation>>,
+        Option<Read<'s, Replay>>,
+        Write<'s, EventChannel<HudEvent>>,
         Read<'s, Time>,
         Read<'s, GameState>,
-        WriteStorage<'s, UiText>,
     );
 
-    fn run(&mut self, (mut map, time, state, mut texts): Self::SystemData) {
+    fn run(&mut self, (sim, replay, mut hud, time, state): Self::SystemData) {
         if *state != GameState::Game {
             return;
         }
-        let map = (&mut map).join().next().unwrap();
-        let time = time.delta_seconds();
-        let error_text = texts.get_mut(map.error_text()).unwrap();
-        if error_text.color[3] >= 1.0 {
-            self.font_timer = 3.;
+        let mut sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
+        self.accumulator += time.delta_seconds() * sim.speed() as f32;
+        while self.accumulator >= DT {
+            // nothing happens anymore once the game is over
+            if sim.is_won() || sim.is_lost() {
+                self.accumulator = 0.0;
+                break;
+            }
+            // a replay stops where its recording did
+            if let Some(replay) = &replay {
+                if sim.tick() >= replay.ticks() {
+                    self.accumulator = 0.0;
+                    break;
+                }
+            }
+            sim.step();
+            self.accumulator -= DT;
+        }
+        if let Some(replay) = &replay {
+            let over = sim.tick() >= replay.ticks() || sim.is_won() || sim.is_lost();
+            if over && !self.replay_over {
+                self.replay_over = true;
+                let desync = match sim.desynced() {
+                    Some(tick) => Some(format!("Replay desynced at tick {}", tick)),
+                    None if sim.checksum() != replay.checksum() => Some(format!(
+                        "Replay desynced: expected checksum {}, got {}",
+                        replay.checksum(),
+                        sim.checksum()
+                    )),
+                    None => None,
+                };
+                match desync {
+                    Some(message) => {
+                        log::warn!("{}", message);
+                        hud.single_write(HudEvent::Message(message));
+                    }
+                    None => log::info!("Replay finished in sync after {} ticks", sim.tick()),
+                }
+            }
         }
-        if self.font_timer > 0. {
-            self.font_timer -= time;
+        let status = HudEvent::status(&sim);
+        for event in status.iter().filter(|event| !self.hud.contains(event)) {
+            hud.single_write(event.clone());
         }
-        error_text.color[3] = self.font_timer / 3.0;
+        self.hud = status;
     }
 }
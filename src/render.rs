@@ -0,0 +1,25 @@
+//! How far up everything is drawn. Sprites with a higher z are drawn on top of
+//! those with a lower one, and the camera only sees what's below it.
+
+/// The layers of the map, each of them getting a band of `MAP_LAYER` in the
+/// order Tiled has them.
+pub const MAP: f32 = 0.0;
+/// How far apart the layers of the map are.
+pub const MAP_LAYER: f32 = 0.1;
+/// The range circles of towers, below anything they could hide.
+pub const RANGES: f32 = 9.0;
+pub const TOWERS: f32 = 10.0;
+pub const RUNNERS: f32 = 11.0;
+/// Fliers are drawn above the runners on the ground.
+pub const FLIERS: f32 = 12.0;
+pub const PROJECTILES: f32 = 13.0;
+/// Health bars and the like, which have to be seen above any runner.
+pub const EFFECTS: f32 = 14.0;
+/// Menus drawn on the map, like the tower selector.
+pub const OVERLAYS: f32 = 15.0;
+pub const CAMERA: f32 = 20.0;
+
+/// Where the Tiled layer with the given index is drawn.
+pub fn map_layer(index: u32) -> f32 {
+    MAP + index as f32 * MAP_LAYER
+}
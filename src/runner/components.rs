@@ -1,84 +1,33 @@
-use amethyst::ecs::prelude::{Component, DenseVecStorage};
+use amethyst::ecs::prelude::Entity;
 
-use crate::tower::Debuff;
+/// The width of the boss health bar when the boss is at full hp.
+pub const BOSS_BAR_WIDTH: f32 = 300.0;
 
-pub struct Runner {
-    road: usize,
-    pos: usize,
-    hp: f32,
-    speed: f32,
-    debuffs: Vec<Debuff>,
-    bounty: usize,
+/// The UI elements which show the hp of the current boss.
+pub struct BossBar {
+    background: Entity,
+    fill: Entity,
+    name: Entity,
 }
 
-impl Runner {
-    pub fn new(road: usize, pos: usize) -> Self {
+impl BossBar {
+    pub fn new(background: Entity, fill: Entity, name: Entity) -> Self {
         Self {
-            road,
-            pos,
-            hp: 100.0,
-            speed: 32.0,
-            debuffs: vec![],
-            bounty: 50,
+            background,
+            fill,
+            name,
         }
     }
 
-    pub fn road(&self) -> usize {
-        self.road
+    pub fn background(&self) -> Entity {
+        self.background
     }
 
-    pub fn pos(&self) -> usize {
-        self.pos
+    pub fn fill(&self) -> Entity {
+        self.fill
     }
 
-    pub fn set_pos(&mut self, pos: usize) {
-        self.pos = pos;
+    pub fn name(&self) -> Entity {
+        self.name
     }
-
-    pub fn hp(&self) -> f32 {
-        self.hp
-    }
-
-    pub fn deal_damage(&mut self, damage: f32) {
-        self.hp -= damage;
-    }
-
-    pub fn speed(&self) -> f32 {
-        self.speed
-    }
-
-    pub fn set_speed(&mut self, speed: f32) {
-        self.speed = speed;
-    }
-
-    pub fn apply_debuff(&mut self, debuff: Option<Debuff>) {
-        if let Some(mut debuff) = debuff {
-            debuff.start(self);
-            self.debuffs.push(debuff);
-        }
-    }
-
-    pub fn tick(&mut self, duration: f32) {
-        let (not_done, done): (Vec<Debuff>, Vec<Debuff>) = self
-            .debuffs
-            .drain(..)
-            .into_iter()
-            .map(|mut d| {
-                d.tick(duration);
-                d
-            })
-            .partition(|d| d.duration() > 0.);
-        self.debuffs = not_done;
-        for mut debuff in done {
-            debuff.end(self);
-        }
-    }
-
-    pub fn bounty(&self) -> usize {
-        self.bounty
-    }
-}
-
-impl Component for Runner {
-    type Storage = DenseVecStorage<Self>;
 }
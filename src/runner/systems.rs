@@ -1,104 +1,334 @@
-use amethyst::core::timing::Time;
+use amethyst::assets::{AssetStorage, Handle, Loader};
+use amethyst::core::Hidden;
 use amethyst::core::Transform;
 use amethyst::derive::SystemDesc;
-use amethyst::ecs::{Entities, Join, Read, ReadStorage, System, SystemData, WriteStorage};
+use amethyst::ecs::{
+    Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, WriteStorage,
+};
+use amethyst::renderer::{SpriteSheet, Texture};
+use amethyst::ui::{UiText, UiTransform};
 
-use crate::{GameState, map::Map, runner::Runner, tower::utils};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    animation::{Animation, Dying},
+    map::{self, Map},
+    render,
+    runner::BOSS_BAR_WIDTH,
+    sim::{Id, Layer, Simulation},
+    GameState,
+};
 use amethyst::renderer::SpriteRender;
 
+/// Keeps an entity around for each runner of the simulation, facing the way
+/// it goes.
 #[derive(SystemDesc)]
-pub struct RunnerSystem;
+pub struct RunnerSystem {
+    // the entity and sprite of each runner
+    runners: HashMap<Id, (Entity, usize)>,
+}
+
+impl RunnerSystem {
+    pub fn new() -> Self {
+        Self {
+            runners: Default::default(),
+        }
+    }
+}
 
 impl<'s> System<'s> for RunnerSystem {
     type SystemData = (
-        WriteStorage<'s, Transform>,
-        WriteStorage<'s, Runner>,
+        Option<Read<'s, Simulation>>,
         ReadStorage<'s, Map>,
-        Read<'s, Time>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, SpriteRender>,
+        WriteStorage<'s, Animation>,
+        WriteStorage<'s, Dying>,
         Read<'s, GameState>,
         Entities<'s>,
     );
 
-    fn run(&mut self, (mut transforms, mut runners, map, time, state, entities): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            sim,
+            map,
+            mut transforms,
+            mut sprites,
+            mut animations,
+            mut dying,
+            state,
+            entities,
+        ): Self::SystemData,
+    ) {
         if *state != GameState::Game {
             return;
         }
+        let sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
         let map = (&map).join().next().unwrap();
-        let road = map.road();
-        let time = time.delta_seconds();
-        for (runner, transform, ent) in (&mut runners, &mut transforms, &entities).join() {
-            runner.tick(time);
-            let road = &road[runner.road()];
-            let runner_pos = runner.pos();
-            let next_pos = road.get(runner_pos + 1);
-            if let Some(next_pos) = next_pos {
-                let target_trans =
-                    next_pos.to_trans(map.tile_width() as usize, map.tile_height() as usize);
-                let mut norm = utils::normalize(transform, &target_trans);
-                if utils::in_range(transform, 1.0, &target_trans) {
-                    runner.set_pos(runner_pos + 1);
+        // runners which died or reached the end
+        let alive: HashSet<Id> = sim.runners().iter().map(|r| r.id()).collect();
+        self.runners.retain(|id, (ent, sprite)| {
+            if alive.contains(id) {
+                return true;
+            }
+            // killed runners stay around until their death animation is over
+            let death = if sim.was_killed(*id) {
+                map.death_animation(*sprite)
+            } else {
+                None
+            };
+            match death {
+                Some(death) => {
+                    animations.insert(*ent, death).unwrap();
+                    dying.insert(*ent, Dying).unwrap();
+                }
+                None => entities.delete(*ent).unwrap(),
+            }
+            false
+        });
+        for runner in sim.runners() {
+            let sprite = runner.sprite_number();
+            let (ent, drawn) = self.runners.entry(runner.id()).or_insert_with(|| {
+                let sprite_render = SpriteRender {
+                    sprite_sheet: map.sprite_sheet_handle(),
+                    sprite_number: sprite,
+                };
+                let mut builder = entities.build_entity().with(sprite_render, &mut sprites);
+                if let Some(walk) = map.animation(sprite) {
+                    builder = builder.with(walk, &mut animations);
+                }
+                (builder.build(), sprite)
+            });
+            // a boss can look different in each phase
+            if *drawn != sprite {
+                *drawn = sprite;
+                if let Some(sprite_render) = sprites.get_mut(*ent) {
+                    sprite_render.sprite_number = sprite;
+                }
+                if let Some(walk) = map.animation(sprite) {
+                    animations.insert(*ent, walk).unwrap();
                 } else {
-                    norm.x *= time * runner.speed();
-                    norm.y *= time * runner.speed();
-                    transform.append_translation(norm);
+                    animations.remove(*ent);
                 }
+            }
+            // fliers are drawn above the ground runners
+            let z = if runner.layer() == Layer::Air {
+                render::FLIERS
+            } else {
+                render::RUNNERS
+            };
+            let mut trans = Transform::default();
+            trans.set_translation_xyz(runner.position().x, runner.position().y, z);
+            // sprites face right, and are mirrored for runners going left
+            if let Some(last) = transforms.get(*ent) {
+                let dx = runner.position().x - last.translation().x;
+                let facing = if dx < 0.0 {
+                    -1.0
+                } else if dx > 0.0 {
+                    1.0
+                } else {
+                    last.scale().x
+                };
+                trans.set_scale([facing, 1.0, 1.0].into());
+            }
+            transforms.insert(*ent, trans).unwrap();
+        }
+    }
+}
+
+/// Keeps the boss health bar up to date.
+#[derive(SystemDesc)]
+pub struct BossSystem;
+
+impl<'s> System<'s> for BossSystem {
+    type SystemData = (
+        Option<Read<'s, Simulation>>,
+        ReadStorage<'s, Map>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, Hidden>,
+        Read<'s, GameState>,
+    );
+
+    fn run(
+        &mut self,
+        (sim, map, mut ui_transforms, mut texts, mut hidden, state): Self::SystemData,
+    ) {
+        if *state != GameState::Game {
+            return;
+        }
+        let sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
+        let map = (&map).join().next().unwrap();
+        let bar = map.boss_bar();
+        let bar_entities = [bar.background(), bar.fill(), bar.name()];
+        if let Some(boss) = sim.runners().iter().find(|r| r.is_boss()) {
+            for e in bar_entities.iter() {
+                hidden.remove(*e);
+            }
+            ui_transforms.get_mut(bar.fill()).unwrap().width =
+                BOSS_BAR_WIDTH * (boss.hp() / boss.max_hp()).max(0.0);
+            texts.get_mut(bar.name()).unwrap().text = if boss.is_immune() {
+                format!("{} (immune)", boss.kind().name())
             } else {
-                // we have reached the end!
-                entities.delete(ent).unwrap();
+                boss.kind().name().to_string()
+            };
+        } else {
+            for e in bar_entities.iter() {
+                hidden.insert(*e, Hidden).unwrap();
             }
         }
     }
 }
 
+/// How wide runner health bars are at full hp.
+const HEALTH_BAR_WIDTH: f32 = 12.0;
+
+/// Draws a health bar above each runner which has lost some hp.
 #[derive(SystemDesc)]
-pub struct SpawnSystem {
-    spawn_timer: f32,
+pub struct HealthBarSystem {
+    // sprite sheets of the background and fill of the bars
+    sheets: Option<(Handle<SpriteSheet>, Handle<SpriteSheet>)>,
+    // the background and fill of the bar of each runner
+    bars: HashMap<Id, (Entity, Entity)>,
 }
 
-impl SpawnSystem {
+impl HealthBarSystem {
     pub fn new() -> Self {
-        Self { spawn_timer: 0.0 }
+        Self {
+            sheets: None,
+            bars: Default::default(),
+        }
+    }
+
+    fn maybe_init(
+        &mut self,
+        loader: &Loader,
+        texture_storage: &AssetStorage<Texture>,
+        sprite_sheet_storage: &AssetStorage<SpriteSheet>,
+    ) {
+        if self.sheets.is_none() {
+            self.sheets = Some((
+                map::create_color_sprite_sheet(
+                    [0.1, 0.1, 0.1, 1.0],
+                    loader,
+                    texture_storage,
+                    sprite_sheet_storage,
+                ),
+                map::create_color_sprite_sheet(
+                    [0.1, 0.9, 0.1, 1.0],
+                    loader,
+                    texture_storage,
+                    sprite_sheet_storage,
+                ),
+            ));
+        }
     }
 }
 
-impl<'s> System<'s> for SpawnSystem {
+impl<'s> System<'s> for HealthBarSystem {
     type SystemData = (
+        Option<Read<'s, Simulation>>,
         ReadStorage<'s, Map>,
-        Read<'s, Time>,
-        WriteStorage<'s, Runner>,
         WriteStorage<'s, Transform>,
         WriteStorage<'s, SpriteRender>,
+        WriteStorage<'s, Hidden>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, AssetStorage<SpriteSheet>>,
         Read<'s, GameState>,
         Entities<'s>,
     );
 
     fn run(
         &mut self,
-        (map, time, mut runners, mut trans, mut sprites, state, entities): Self::SystemData,
+        (
+            sim,
+            map,
+            mut transforms,
+            mut sprites,
+            mut hidden,
+            loader,
+            texture_storage,
+            sprite_sheet_storage,
+            state,
+            entities,
+        ): Self::SystemData,
     ) {
         if *state != GameState::Game {
             return;
         }
+        let sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
         let map = (&map).join().next().unwrap();
-        let time = time.delta_seconds();
-        self.spawn_timer += time;
-        if self.spawn_timer > 1.0 {
-            let sprite = SpriteRender {
-                sprite_sheet: map.sprite_sheet_handle(),
-                sprite_number: 125,
-            };
-            let r = rand::random::<usize>() % map.road().len();
-            entities
-                .build_entity()
-                .with(Runner::new(r, 0), &mut runners)
-                .with(
-                    map.road()[r][0]
-                        .to_trans(map.tile_width() as usize, map.tile_height() as usize),
-                    &mut trans,
-                )
-                .with(sprite, &mut sprites)
-                .build();
-            self.spawn_timer = 0.0;
+        self.maybe_init(&loader, &texture_storage, &sprite_sheet_storage);
+        let (background_sheet, fill_sheet) = self.sheets.clone().unwrap();
+
+        // bars of runners which are gone have to go as well
+        let alive: HashSet<Id> = sim.runners().iter().map(|r| r.id()).collect();
+        self.bars.retain(|id, (background, fill)| {
+            if !alive.contains(id) {
+                entities.delete(*background).unwrap();
+                entities.delete(*fill).unwrap();
+            }
+            alive.contains(id)
+        });
+
+        for runner in sim.runners() {
+            let (background, fill) = *self.bars.entry(runner.id()).or_insert_with(|| {
+                let background = entities
+                    .build_entity()
+                    .with(
+                        SpriteRender {
+                            sprite_sheet: background_sheet.clone(),
+                            sprite_number: 0,
+                        },
+                        &mut sprites,
+                    )
+                    .build();
+                let fill = entities
+                    .build_entity()
+                    .with(
+                        SpriteRender {
+                            sprite_sheet: fill_sheet.clone(),
+                            sprite_number: 0,
+                        },
+                        &mut sprites,
+                    )
+                    .build();
+                (background, fill)
+            });
+            let fraction = (runner.hp() / runner.max_hp()).max(0.0);
+            // there's no point in showing a full bar
+            if fraction >= 1.0 {
+                hidden.insert(background, Hidden).unwrap();
+                hidden.insert(fill, Hidden).unwrap();
+            } else {
+                hidden.remove(background);
+                hidden.remove(fill);
+            }
+            let mut trans = Transform::default();
+            trans.set_translation_xyz(
+                runner.position().x,
+                runner.position().y + (map.tile_height() / 2 + 2) as f32,
+                render::EFFECTS,
+            );
+            trans.set_scale([HEALTH_BAR_WIDTH, 2.0, 1.0].into());
+            transforms.insert(background, trans.clone()).unwrap();
+            // the fill shrinks towards the left edge of the bar
+            let width = HEALTH_BAR_WIDTH * fraction;
+            trans.translation_mut().x -= (HEALTH_BAR_WIDTH - width) / 2.0;
+            trans.translation_mut().z += 0.01;
+            trans.set_scale([width, 2.0, 1.0].into());
+            transforms.insert(fill, trans).unwrap();
         }
     }
 }
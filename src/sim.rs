@@ -0,0 +1,30 @@
+//! The game itself: runners, towers, missles, waves and the player's gold and
+//! lives. Nothing in here knows about amethyst, so a `Simulation` can be
+//! stepped without a window (e.g. in tests or balance experiments). The
+//! systems of the other modules only draw it and turn input into `Command`s.
+mod archetype;
+mod bot;
+mod level;
+mod replay;
+mod runner;
+mod save;
+mod simulation;
+mod stats;
+mod tower;
+mod validate;
+mod waves;
+
+pub use archetype::*;
+pub use bot::*;
+pub use level::*;
+pub use replay::*;
+pub use runner::*;
+pub use save::*;
+pub use simulation::*;
+pub use stats::*;
+pub use tower::*;
+pub use validate::*;
+pub use waves::*;
+
+/// Identifies a runner, tower or missle of a simulation.
+pub type Id = u32;
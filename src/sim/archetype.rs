@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, io, path::Path};
+
+use crate::sim::{Ability, LevelLoadError, Phase, RunnerKind};
+
+/// The archetypes the game comes with, for levels without their own.
+const RUNNERS: &str = include_str!("../../assets/runners.ron");
+
+/// What runners of a kind can do, besides walking to the exit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Archetype {
+    #[serde(default)]
+    abilities: Vec<Ability>,
+    /// Only bosses have phases, ordered by when they start.
+    #[serde(default)]
+    phases: Vec<Phase>,
+}
+
+impl Archetype {
+    pub fn new(abilities: Vec<Ability>, phases: Vec<Phase>) -> Self {
+        Self { abilities, phases }
+    }
+
+    pub fn abilities(&self) -> &Vec<Ability> {
+        &self.abilities
+    }
+
+    pub fn phases(&self) -> &Vec<Phase> {
+        &self.phases
+    }
+}
+
+/// The archetype of every kind of runner. Kinds without one have no
+/// abilities.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Archetypes(BTreeMap<RunnerKind, Archetype>);
+
+impl Archetypes {
+    pub fn new(archetypes: BTreeMap<RunnerKind, Archetype>) -> Self {
+        Self(archetypes)
+    }
+
+    /// Loads archetypes from a RON file.
+    pub fn load(path: &Path) -> Result<Self, ron::de::Error> {
+        let file = std::fs::File::open(path)?;
+        ron::de::from_reader(io::BufReader::new(file))
+    }
+
+    /// The archetypes of the level at `path`, from the `runners.ron` next to
+    /// it, or the ones the game comes with if there is none.
+    pub fn for_level(path: &Path) -> Result<Self, LevelLoadError> {
+        match std::fs::File::open(path.with_file_name("runners.ron")) {
+            Ok(file) => ron::de::from_reader(io::BufReader::new(file))
+                .map(Self)
+                .map_err(LevelLoadError::Runners),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get(&self, kind: RunnerKind) -> &Archetype {
+        static NONE: Archetype = Archetype {
+            abilities: Vec::new(),
+            phases: Vec::new(),
+        };
+        self.0.get(&kind).unwrap_or(&NONE)
+    }
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        Self(ron::de::from_str(RUNNERS).expect("the bundled runners.ron is broken"))
+    }
+}
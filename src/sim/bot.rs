@@ -0,0 +1,95 @@
+use crate::sim::{Command, Coord, Level, Simulation, TowerKind};
+
+/// A greedy player, e.g. to check that a level can be beaten. It builds the
+/// tower which deals the most damage for its cost on the build points which
+/// cover the most road, and upgrades towers when it has nothing to build.
+///
+/// Only roads are taken into account, not the flights of flying runners: the
+/// bot can't tell whether a level's waves have any, and may leave them to
+/// towers which can't shoot at the air.
+pub struct Bot {
+    kind: TowerKind,
+    // the build points worth building on, the ones covering the most road first
+    build_points: Vec<Coord>,
+}
+
+impl Bot {
+    pub fn new(level: &Level) -> Self {
+        let kind = best(TowerKind::buildable().to_vec()).unwrap();
+        // runners take any of the roads, so tiles many of them share (e.g.
+        // next to the exit) are worth more
+        let coverage = |pos: &Coord| {
+            let center = level.center(*pos);
+            level
+                .roads()
+                .iter()
+                .flat_map(|r| r.tiles().iter())
+                .filter(|tile| level.center(**tile).distance(&center) <= kind.radius())
+                .count()
+        };
+        let mut build_points: Vec<Coord> = level
+            .build_points()
+            .iter()
+            .cloned()
+            .filter(|pos| coverage(pos) > 0)
+            .collect();
+        // the sort is stable, so points covering as much are kept in the order of the level
+        build_points.sort_by_key(|pos| std::cmp::Reverse(coverage(pos)));
+        Self { kind, build_points }
+    }
+
+    /// What the bot wants to do next, if it can afford anything.
+    pub fn think(&self, sim: &Simulation) -> Option<Command> {
+        let empty = self
+            .build_points
+            .iter()
+            .find(|pos| sim.tower_at(**pos).is_none());
+        if let Some(pos) = empty {
+            // save up for the next tower, rather than upgrading
+            return if self.kind.cost() <= sim.gold() {
+                Some(Command::Build {
+                    pos: *pos,
+                    kind: self.kind,
+                })
+            } else {
+                None
+            };
+        }
+        self.build_points.iter().find_map(|pos| {
+            let tower = sim.tower_at(*pos)?;
+            let kind = best(tower.upgrades())?;
+            if kind.cost() <= sim.gold() {
+                Some(Command::Upgrade { pos: *pos, kind })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Does everything the bot wants to do before the next step of `sim`.
+    pub fn act(&self, sim: &mut Simulation) {
+        while let Some(command) = self.think(sim) {
+            if sim.apply(command).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Plays `sim` until the game is won or lost, or `max_ticks` have passed.
+    /// Returns whether the game was won.
+    pub fn play(&self, sim: &mut Simulation, max_ticks: u64) -> bool {
+        while !sim.is_won() && !sim.is_lost() && sim.tick() < max_ticks {
+            self.act(sim);
+            sim.step();
+        }
+        sim.is_won()
+    }
+}
+
+/// The tower which deals the most damage per second for its cost.
+fn best(kinds: Vec<TowerKind>) -> Option<TowerKind> {
+    let value = |kind: &TowerKind| kind.dps() / kind.cost() as f32;
+    kinds
+        .into_iter()
+        .max_by(|a, b| value(a).partial_cmp(&value(b)).unwrap())
+}
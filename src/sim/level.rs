@@ -0,0 +1,817 @@
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, fmt, io, path::Path};
+
+use crate::sim::{validate_map, Layer, MapError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Coord {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Coord {
+    pub fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn distance(&self, other: &Coord) -> f32 {
+        (((self.x as isize - other.x as isize).pow(2) + (self.y as isize - other.y as isize).pow(2))
+            as f32)
+            .sqrt()
+    }
+
+    /// The center of the tile in world coordinates.
+    pub fn center(&self, tile_w: usize, tile_h: usize) -> Point {
+        Point::new(
+            (self.x * tile_w) as f32 + (tile_w / 2) as f32,
+            (self.y * tile_h) as f32 + (tile_h / 2) as f32,
+        )
+    }
+}
+
+/// A position in world coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn distance(&self, other: &Point) -> f32 {
+        ((self.x - other.x).powf(2.0) + (self.y - other.y).powf(2.0)).sqrt()
+    }
+
+    /// Moves `step` units towards `dest`, without going past it.
+    pub fn step_towards(&mut self, dest: &Point, step: f32) {
+        let (dx, dy) = (dest.x - self.x, dest.y - self.y);
+        let magnitude = (dx * dx + dy * dy).sqrt();
+        if magnitude <= step {
+            *self = *dest;
+        } else {
+            self.x += dx / magnitude * step;
+            self.y += dy / magnitude * step;
+        }
+    }
+}
+
+/// A tile from which runners enter the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spawn {
+    id: usize,
+    pos: Coord,
+}
+
+impl Spawn {
+    pub fn new(id: usize, pos: Coord) -> Self {
+        Self { id, pos }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn pos(&self) -> Coord {
+        self.pos
+    }
+}
+
+/// A tile through which runners leave the map, costing the player `penalty`
+/// lives for each runner that reaches it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exit {
+    id: usize,
+    pos: Coord,
+    penalty: usize,
+}
+
+impl Exit {
+    pub fn new(id: usize, pos: Coord, penalty: usize) -> Self {
+        Self { id, pos, penalty }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn pos(&self) -> Coord {
+        self.pos
+    }
+
+    pub fn penalty(&self) -> usize {
+        self.penalty
+    }
+}
+
+/// A path from a spawn to an exit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Road {
+    spawn: usize,
+    exit: usize,
+    tiles: Vec<Coord>,
+    // runners follow these instead of going through the center of each tile,
+    // if there are any
+    points: Vec<Point>,
+}
+
+impl Road {
+    pub fn new(spawn: usize, exit: usize, tiles: Vec<Coord>) -> Self {
+        Self {
+            spawn,
+            exit,
+            tiles,
+            points: vec![],
+        }
+    }
+
+    /// A road which doesn't follow the tile grid. Runners go from point to
+    /// point, crossing `tiles` on their way.
+    pub fn along(spawn: usize, exit: usize, points: Vec<Point>, tiles: Vec<Coord>) -> Self {
+        Self {
+            spawn,
+            exit,
+            tiles,
+            points,
+        }
+    }
+
+    pub fn spawn(&self) -> usize {
+        self.spawn
+    }
+
+    pub fn exit(&self) -> usize {
+        self.exit
+    }
+
+    pub fn tiles(&self) -> &Vec<Coord> {
+        &self.tiles
+    }
+
+    pub fn points(&self) -> &Vec<Point> {
+        &self.points
+    }
+}
+
+/// A path from a spawn to an exit that flying runners take, given in world
+/// coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flight {
+    spawn: usize,
+    exit: usize,
+    points: Vec<Point>,
+}
+
+impl Flight {
+    pub fn new(spawn: usize, exit: usize, points: Vec<Point>) -> Self {
+        Self {
+            spawn,
+            exit,
+            points,
+        }
+    }
+
+    pub fn spawn(&self) -> usize {
+        self.spawn
+    }
+
+    pub fn exit(&self) -> usize {
+        self.exit
+    }
+
+    pub fn points(&self) -> &Vec<Point> {
+        &self.points
+    }
+}
+
+/// Everything the simulation needs to know about a map.
+#[derive(Debug, Clone, Default)]
+pub struct Level {
+    width: usize,
+    height: usize,
+    tile_width: usize,
+    tile_height: usize,
+    roads: Vec<Road>,
+    flights: Vec<Flight>,
+    spawns: Vec<Spawn>,
+    exits: Vec<Exit>,
+    build_points: Vec<Coord>,
+}
+
+impl Level {
+    pub fn new(
+        (width, height): (usize, usize),
+        (tile_width, tile_height): (usize, usize),
+        roads: Vec<Road>,
+        flights: Vec<Flight>,
+        spawns: Vec<Spawn>,
+        exits: Vec<Exit>,
+        build_points: Vec<Coord>,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            tile_width,
+            tile_height,
+            roads,
+            flights,
+            spawns,
+            exits,
+            build_points,
+        }
+    }
+
+    /// Finds the roads, spawns, exits and build points of a Tiled map, or
+    /// everything which is wrong with it.
+    pub fn from_tiled(map: &tiled::Map) -> Result<Self, Vec<MapError>> {
+        let errors = validate_map(map);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let tiles = Tiles::read(map);
+        let exit_coords: Vec<Coord> = tiles.exits.iter().map(|e| e.pos()).collect();
+        let mut paths = vec![];
+        for spawn in &tiles.spawns {
+            for path in gather_paths(vec![spawn.pos()], &exit_coords, &tiles.road_map) {
+                let end = *path.last().unwrap();
+                let exit = tiles.exits.iter().find(|e| e.pos() == end).unwrap();
+                paths.push(Road::new(spawn.id(), exit.id(), path));
+            }
+        }
+        paths.extend(gather_roads(map, &tiles.spawns, &tiles.exits));
+        let flights = gather_flights(map, &tiles.spawns, &tiles.exits);
+        Ok(Self::new(
+            (map.width as usize, map.height as usize),
+            (map.tile_width as usize, map.tile_height as usize),
+            paths,
+            flights,
+            tiles.spawns,
+            tiles.exits,
+            tiles.build_points,
+        ))
+    }
+
+    /// The size of the map in tiles.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn tile_width(&self) -> usize {
+        self.tile_width
+    }
+
+    pub fn tile_height(&self) -> usize {
+        self.tile_height
+    }
+
+    pub fn roads(&self) -> &Vec<Road> {
+        &self.roads
+    }
+
+    pub fn flights(&self) -> &Vec<Flight> {
+        &self.flights
+    }
+
+    pub fn spawns(&self) -> &Vec<Spawn> {
+        &self.spawns
+    }
+
+    pub fn exits(&self) -> &Vec<Exit> {
+        &self.exits
+    }
+
+    pub fn exit(&self, id: usize) -> Option<&Exit> {
+        self.exits.iter().find(|e| e.id() == id)
+    }
+
+    pub fn build_points(&self) -> &Vec<Coord> {
+        &self.build_points
+    }
+
+    /// The center of a tile in world coordinates.
+    pub fn center(&self, pos: Coord) -> Point {
+        pos.center(self.tile_width, self.tile_height)
+    }
+
+    /// The tile which contains a point given in world coordinates.
+    pub fn coord_at(&self, point: Point) -> Option<Coord> {
+        if point.x < 0. || point.y < 0. {
+            return None;
+        }
+        let (x, y) = (
+            point.x as usize / self.tile_width,
+            point.y as usize / self.tile_height,
+        );
+        if x < self.width && y < self.height {
+            Some(Coord::new(x, y))
+        } else {
+            None
+        }
+    }
+
+    /// The indices of the paths runners on `layer` can take from the spawn
+    /// with id `spawn`.
+    pub fn paths_from(&self, layer: Layer, spawn: usize) -> Vec<usize> {
+        match layer {
+            Layer::Ground => self
+                .roads
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.spawn() == spawn)
+                .map(|(i, _)| i)
+                .collect(),
+            Layer::Air => self
+                .flights
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.spawn() == spawn)
+                .map(|(i, _)| i)
+                .collect(),
+        }
+    }
+
+    /// The id of the exit at the end of a path of `layer`.
+    pub fn path_exit(&self, layer: Layer, path: usize) -> usize {
+        match layer {
+            Layer::Ground => self.roads[path].exit(),
+            Layer::Air => self.flights[path].exit(),
+        }
+    }
+
+    /// Where waypoint `pos` of a path of `layer` is, if the path is that long.
+    pub fn waypoint(&self, layer: Layer, path: usize, pos: usize) -> Option<Point> {
+        match layer {
+            Layer::Ground => {
+                let road = &self.roads[path];
+                if road.points().is_empty() {
+                    road.tiles().get(pos).map(|c| self.center(*c))
+                } else {
+                    road.points().get(pos).cloned()
+                }
+            }
+            Layer::Air => self.flights[path].points().get(pos).cloned(),
+        }
+    }
+}
+
+/// Why a level couldn't be loaded.
+#[derive(Debug)]
+pub enum LevelLoadError {
+    Io(io::Error),
+    Parse(tiled::TiledError),
+    /// The waves of the level couldn't be read.
+    Waves(ron::de::Error),
+    /// The archetypes of the runners of the level couldn't be read.
+    Runners(ron::de::Error),
+    /// The map was read, but it can't be played.
+    Invalid(Vec<MapError>),
+}
+
+impl fmt::Display for LevelLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelLoadError::Io(e) => write!(f, "{}", e),
+            LevelLoadError::Parse(e) => write!(f, "The map is corrupted: {:?}", e),
+            LevelLoadError::Waves(e) => write!(f, "The waves are corrupted: {}", e),
+            LevelLoadError::Runners(e) => write!(f, "The runners are corrupted: {}", e),
+            LevelLoadError::Invalid(errors) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", errors.join("\n"))
+            }
+        }
+    }
+}
+
+impl From<io::Error> for LevelLoadError {
+    fn from(e: io::Error) -> Self {
+        LevelLoadError::Io(e)
+    }
+}
+
+impl From<tiled::TiledError> for LevelLoadError {
+    fn from(e: tiled::TiledError) -> Self {
+        LevelLoadError::Parse(e)
+    }
+}
+
+impl From<Vec<MapError>> for LevelLoadError {
+    fn from(errors: Vec<MapError>) -> Self {
+        LevelLoadError::Invalid(errors)
+    }
+}
+
+/// Reads a Tiled map and the level it describes.
+pub fn load_level(path: &Path) -> Result<(tiled::Map, Level), LevelLoadError> {
+    let file = std::fs::File::open(path)?;
+    let map = tiled::parse(io::BufReader::new(file))?;
+    let level = Level::from_tiled(&map)?;
+    Ok((map, level))
+}
+
+/// The index of the tileset a gid of `map` comes from, and the id of the tile
+/// in that tileset.
+pub fn tileset_tile(map: &tiled::Map, gid: u32) -> Option<(usize, u32)> {
+    map.tilesets
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.first_gid <= gid)
+        .max_by_key(|(_, t)| t.first_gid)
+        .map(|(i, t)| (i, gid - t.first_gid))
+}
+
+/// What the tiles of a Tiled map are, before checking whether they make a
+/// playable level.
+pub(crate) struct Tiles {
+    /// The directions runners can go from each tile, indexed by [x][y].
+    pub road_map: Vec<Vec<u8>>,
+    /// Where the road tiles are, not counting spawns and exits.
+    pub roads: Vec<Coord>,
+    pub spawns: Vec<Spawn>,
+    pub exits: Vec<Exit>,
+    pub build_points: Vec<Coord>,
+}
+
+impl Tiles {
+    /// Reads the tiles of `map`, looking their properties up in the tileset
+    /// each of them comes from.
+    pub fn read(map: &tiled::Map) -> Self {
+        // which gids are our roads, and where they lead
+        let mut directions = HashMap::new();
+        // which gids are our construction points
+        let mut construction_points = vec![];
+        // which gids are starting points, and the spawn id they were given
+        let mut start_points = HashMap::new();
+        // which gids are end points, their exit id and leak penalty
+        let mut end_points = HashMap::new();
+        let tiles = map.tilesets.iter().flat_map(|t| {
+            t.tiles
+                .iter()
+                .map(move |tile| (t.first_gid + tile.id, tile))
+        });
+        for (gid, tile) in tiles {
+            if tile.properties.contains_key("road") {
+                let mut dir: u8 = 0b0000;
+                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("up") {
+                    dir |= 0b0001;
+                }
+                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("right") {
+                    dir |= 0b0010;
+                }
+                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("down") {
+                    dir |= 0b0100;
+                }
+                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("left") {
+                    dir |= 0b1000;
+                }
+                directions.insert(gid, dir);
+            } else if tile.properties.contains_key("construction-point") {
+                construction_points.push(gid);
+            } else if tile.properties.contains_key("start-point") {
+                start_points.insert(gid, int_property(&tile.properties, "spawn-id"));
+            } else if tile.properties.contains_key("end-point") {
+                end_points.insert(
+                    gid,
+                    (
+                        int_property(&tile.properties, "exit-id"),
+                        int_property(&tile.properties, "leak-penalty").unwrap_or(1),
+                    ),
+                );
+            }
+        }
+        let mut tiles = Self {
+            road_map: vec![vec![0; map.height as usize]; map.width as usize],
+            roads: vec![],
+            spawns: vec![],
+            exits: vec![],
+            build_points: vec![],
+        };
+        // the spawns and exits found, with the ids they were given if any
+        let mut spawns = vec![];
+        let mut exits = vec![];
+        for layer in map.layers.iter().rev() {
+            for (y, row) in layer.tiles.iter().rev().enumerate() {
+                for (x, &tile) in row.iter().enumerate() {
+                    // Do nothing with empty tiles
+                    if tile.gid == 0 {
+                        continue;
+                    }
+
+                    let pos = Coord::new(x, y);
+                    if let Some(dir) = directions.get(&tile.gid) {
+                        tiles.road_map[x][y] = *dir;
+                        tiles.roads.push(pos);
+                    } else if let Some(id) = start_points.get(&tile.gid) {
+                        spawns.push((*id, pos));
+                        tiles.road_map[x][y] = 0b1111;
+                    } else if let Some((id, penalty)) = end_points.get(&tile.gid) {
+                        exits.push((*id, (pos, *penalty)));
+                        tiles.road_map[x][y] = 0b1111;
+                    } else if construction_points.contains(&tile.gid) {
+                        tiles.build_points.push(pos);
+                    }
+                }
+            }
+        }
+        tiles.read_objects(map, &mut spawns, &mut exits);
+        tiles.spawns = number(spawns)
+            .into_iter()
+            .map(|(id, pos)| Spawn::new(id, pos))
+            .collect();
+        tiles.exits = number(exits)
+            .into_iter()
+            .map(|(id, (pos, penalty))| Exit::new(id, pos, penalty))
+            .collect();
+        tiles
+    }
+
+    /// Adds the spawns and exits marked by point objects, and the build
+    /// points of the zones marked by rectangles.
+    fn read_objects(
+        &mut self,
+        map: &tiled::Map,
+        spawns: &mut Vec<(Option<usize>, Coord)>,
+        exits: &mut Vec<(Option<usize>, (Coord, usize))>,
+    ) {
+        let objects = map.object_groups.iter().flat_map(|g| g.objects.iter());
+        let mut no_build = vec![];
+        for object in objects {
+            let pos = match object_coord(map, object.x, object.y) {
+                Some(pos) => pos,
+                None => continue,
+            };
+            match object.obj_type.as_str() {
+                "spawn" => {
+                    spawns.push((int_property(&object.properties, "spawn-id"), pos));
+                    self.road_map[pos.x][pos.y] = 0b1111;
+                }
+                "exit" => {
+                    let id = int_property(&object.properties, "exit-id");
+                    let penalty = int_property(&object.properties, "leak-penalty").unwrap_or(1);
+                    exits.push((id, (pos, penalty)));
+                    self.road_map[pos.x][pos.y] = 0b1111;
+                }
+                "build-zone" => {
+                    for pos in tiles_in(map, object) {
+                        if !self.build_points.contains(&pos) {
+                            self.build_points.push(pos);
+                        }
+                    }
+                }
+                "no-build" => no_build.extend(tiles_in(map, object)),
+                _ => {}
+            }
+        }
+        self.build_points.retain(|pos| !no_build.contains(pos));
+    }
+}
+
+/// Gives the spawns or exits found without an id the ids after the highest
+/// one given explicitly, in the order they were found.
+fn number<T>(found: Vec<(Option<usize>, T)>) -> Vec<(usize, T)> {
+    let mut next = found
+        .iter()
+        .filter_map(|(id, _)| *id)
+        .max()
+        .map_or(0, |id| id + 1);
+    found
+        .into_iter()
+        .map(|(id, t)| {
+            let id = id.unwrap_or_else(|| {
+                next += 1;
+                next - 1
+            });
+            (id, t)
+        })
+        .collect()
+}
+
+/// The tile containing a point given in Tiled's pixel coordinates, if it is on
+/// the map.
+fn object_coord(map: &tiled::Map, x: f32, y: f32) -> Option<Coord> {
+    let (width, height) = (map.width * map.tile_width, map.height * map.tile_height);
+    if x < 0. || y < 0. || x >= width as f32 || y >= height as f32 {
+        return None;
+    }
+    // tiled's y axis points down, ours points up
+    let row = y as usize / map.tile_height as usize;
+    Some(Coord::new(
+        x as usize / map.tile_width as usize,
+        map.height as usize - 1 - row,
+    ))
+}
+
+/// The tiles whose center is inside a rectangle object.
+fn tiles_in(map: &tiled::Map, object: &tiled::Object) -> Vec<Coord> {
+    let (tile_w, tile_h) = (map.tile_width as f32, map.tile_height as f32);
+    let mut tiles = vec![];
+    for y in 0..map.height {
+        for x in 0..map.width {
+            // in Tiled's pixel coordinates
+            let center_x = (x as f32 + 0.5) * tile_w;
+            let center_y = (y as f32 + 0.5) * tile_h;
+            if center_x >= object.x
+                && center_x < object.x + object.width
+                && center_y >= object.y
+                && center_y < object.y + object.height
+            {
+                tiles.push(Coord::new(x as usize, (map.height - 1 - y) as usize));
+            }
+        }
+    }
+    tiles
+}
+
+/// The points of a polyline object in world coordinates, if it is one.
+fn polyline(map: &tiled::Map, object: &tiled::Object) -> Option<Vec<Point>> {
+    // tiled's y axis points down, ours points up
+    let map_height = (map.height * map.tile_height) as f32;
+    match &object.shape {
+        tiled::ObjectShape::Polyline { points } if points.len() > 1 => Some(
+            points
+                .iter()
+                .map(|(x, y)| Point::new(object.x + x, map_height - (object.y + y)))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// The spawn closest to the start of a path and the exit closest to its end.
+fn path_ends<'a>(
+    map: &tiled::Map,
+    points: &[Point],
+    spawns: &'a [Spawn],
+    exits: &'a [Exit],
+) -> Option<(&'a Spawn, &'a Exit)> {
+    let (tile_w, tile_h) = (map.tile_width as usize, map.tile_height as usize);
+    let to_world = |c: Coord| c.center(tile_w, tile_h);
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let spawn = spawns.iter().min_by(|a, b| {
+        to_world(a.pos())
+            .distance(&first)
+            .partial_cmp(&to_world(b.pos()).distance(&first))
+            .unwrap()
+    });
+    let exit = exits.iter().min_by(|a, b| {
+        to_world(a.pos())
+            .distance(&last)
+            .partial_cmp(&to_world(b.pos()).distance(&last))
+            .unwrap()
+    });
+    match (spawn, exit) {
+        (Some(spawn), Some(exit)) => Some((spawn, exit)),
+        _ => None,
+    }
+}
+
+/// How many points each segment of a road is smoothed into.
+const CURVE_STEPS: usize = 8;
+
+/// Gets the roads drawn as polyline objects of type `road`, which connect the
+/// spawn and exit closest to their ends like flights do. Runners follow a
+/// smooth curve through the points of the line, unless it has a `smooth`
+/// property set to false.
+pub(crate) fn gather_roads(map: &tiled::Map, spawns: &[Spawn], exits: &[Exit]) -> Vec<Road> {
+    let mut roads = vec![];
+    let objects = map.object_groups.iter().flat_map(|g| g.objects.iter());
+    for object in objects.filter(|o| o.obj_type == "road") {
+        let points = match polyline(map, object) {
+            Some(points) => points,
+            None => continue,
+        };
+        let (spawn, exit) = match path_ends(map, &points, spawns, exits) {
+            Some(ends) => ends,
+            None => continue,
+        };
+        let points = match object.properties.get("smooth") {
+            Some(tiled::PropertyValue::BoolValue(false)) => points,
+            _ => smooth(&points, CURVE_STEPS),
+        };
+        let (tile_w, tile_h) = (map.tile_width as f32, map.tile_height as f32);
+        let mut tiles: Vec<Coord> = vec![];
+        for point in points.iter().filter(|p| p.x >= 0. && p.y >= 0.) {
+            let pos = Coord::new((point.x / tile_w) as usize, (point.y / tile_h) as usize);
+            if tiles.last() != Some(&pos) {
+                tiles.push(pos);
+            }
+        }
+        roads.push(Road::along(spawn.id(), exit.id(), points, tiles));
+    }
+    roads
+}
+
+/// Turns a polyline into a Catmull-Rom curve going through each of its
+/// points, with `steps` points for each segment of the line.
+pub fn smooth(points: &[Point], steps: usize) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let last = points.len() - 1;
+    let mut curve = vec![points[0]];
+    for i in 0..last {
+        let p0 = points[i.saturating_sub(1)];
+        let (p1, p2) = (points[i], points[i + 1]);
+        let p3 = points[(i + 2).min(last)];
+        let at = |t: f32, a: f32, b: f32, c: f32, d: f32| {
+            0.5 * (2.0 * b
+                + (c - a) * t
+                + (2.0 * a - 5.0 * b + 4.0 * c - d) * t * t
+                + (3.0 * b - a - 3.0 * c + d) * t * t * t)
+        };
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            curve.push(Point::new(
+                at(t, p0.x, p1.x, p2.x, p3.x),
+                at(t, p0.y, p1.y, p2.y, p3.y),
+            ));
+        }
+    }
+    curve
+}
+
+/// Gets the paths flying runners take. These are the polyline objects of type
+/// `flight`, which connect the spawn and exit closest to their ends. Fliers
+/// coming from a spawn which has no such polyline fly in a straight line
+/// towards each exit.
+fn gather_flights(map: &tiled::Map, spawns: &[Spawn], exits: &[Exit]) -> Vec<Flight> {
+    let (tile_w, tile_h) = (map.tile_width as usize, map.tile_height as usize);
+    let to_world = |c: Coord| c.center(tile_w, tile_h);
+    let mut flights = vec![];
+    let objects = map.object_groups.iter().flat_map(|g| g.objects.iter());
+    for object in objects.filter(|o| o.obj_type == "flight") {
+        let points = match polyline(map, object) {
+            Some(points) => points,
+            None => continue,
+        };
+        if let Some((spawn, exit)) = path_ends(map, &points, spawns, exits) {
+            flights.push(Flight::new(spawn.id(), exit.id(), points));
+        }
+    }
+    for spawn in spawns {
+        if flights.iter().any(|f| f.spawn() == spawn.id()) {
+            continue;
+        }
+        for exit in exits {
+            let points = vec![to_world(spawn.pos()), to_world(exit.pos())];
+            flights.push(Flight::new(spawn.id(), exit.id(), points));
+        }
+    }
+    flights
+}
+
+/// Reads an integer tile property, if the tile has one.
+fn int_property(properties: &tiled::Properties, name: &str) -> Option<usize> {
+    if let Some(tiled::PropertyValue::IntValue(value)) = properties.get(name) {
+        Some(*value as usize)
+    } else {
+        None
+    }
+}
+
+/// Gets all valid paths that a runner can take to reach any of the destinations.
+pub fn gather_paths(path: Vec<Coord>, dests: &[Coord], map: &[Vec<u8>]) -> Vec<Vec<Coord>> {
+    static DIRECTIONS: [(isize, isize, u8); 4] = [
+        (0, 1, 0b0001),
+        (0, -1, 0b0100),
+        (1, 0, 0b0010),
+        (-1, 0, 0b1000),
+    ];
+    // where we are coming from
+    let origin = *path.iter().last().unwrap();
+    // did we just finish a path? if so return it
+    if dests.contains(&origin) {
+        return vec![path];
+    }
+    let mut neighbours = Vec::with_capacity(4);
+    // up, down, right, left
+    for offset in &DIRECTIONS {
+        let new_c = (origin.x as isize + offset.0, origin.y as isize + offset.1);
+        // if we can go in that direction
+        if new_c.0 >= 0
+            && new_c.1 >= 0
+            && (new_c.0 as usize) < map.len()
+            && (new_c.1 as usize) < map[0].len()
+            // if we can actually move in that direction!
+            && (map[origin.x][origin.y] & offset.2) > 0
+        {
+            let new_coord = Coord::new(new_c.0 as usize, new_c.1 as usize);
+            // make sure we don't create a cycle!
+            if !path.contains(&new_coord) {
+                neighbours.push(Coord::new(new_c.0 as usize, new_c.1 as usize));
+            }
+        }
+    }
+    let mut final_paths = vec![];
+    for n in neighbours {
+        // a new path from what we have so far + a neighbour that we can visit
+        let new_path = path.iter().cloned().chain(std::iter::once(n)).collect();
+        let paths = gather_paths(new_path, dests, map);
+        if !path.is_empty() {
+            final_paths.extend(paths);
+        }
+    }
+    final_paths
+}
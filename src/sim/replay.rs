@@ -0,0 +1,66 @@
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::sim::{Command, Simulation};
+
+/// Everything needed to play a game again: the level, the seed and the
+/// commands of the player. The checksum of the game when the recording
+/// stopped tells whether the playback went the same way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    level: PathBuf,
+    seed: u64,
+    commands: Vec<(u64, Command)>,
+    /// The tick the recording stopped at.
+    ticks: u64,
+    checksum: u64,
+}
+
+impl Replay {
+    /// Records the game `sim` has gone through so far on `level`.
+    pub fn record(level: PathBuf, sim: &Simulation) -> Self {
+        Self {
+            level,
+            seed: sim.seed(),
+            commands: sim.history().clone(),
+            ticks: sim.tick(),
+            checksum: sim.checksum(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ron::de::Error> {
+        let file = std::fs::File::open(path)?;
+        ron::de::from_reader(std::io::BufReader::new(file))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let replay = ron::ser::to_string_pretty(self, PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        std::fs::write(path, replay)
+    }
+
+    pub fn level(&self) -> &Path {
+        &self.level
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn commands(&self) -> &Vec<(u64, Command)> {
+        &self.commands
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn checksum(&self) -> u64 {
+        self.checksum
+    }
+}
@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sim::{Archetype, Id, Point};
+
+/// Whether a runner walks along the roads or flies over them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Layer {
+    Ground,
+    Air,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Ability {
+    /// Restores `amount` hp per second to the other runners within `radius`.
+    Heal { radius: f32, amount: f32 },
+    /// Leaves `count` runners of `kind` behind when the runner dies.
+    Split { kind: RunnerKind, count: usize },
+    /// Absorbs the first `amount` damage dealt to the runner.
+    Shield { amount: f32 },
+    /// Only towers which can detect stealth can shoot at the runner.
+    Stealth,
+}
+
+/// A stage of a boss fight, which starts once the boss is down to `hp` (as a
+/// fraction of its full hp).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Phase {
+    pub hp: f32,
+    /// By how much the speed of the boss is multiplied.
+    #[serde(default = "unchanged_speed")]
+    pub speed: f32,
+    /// Runners which the boss calls in when the phase starts.
+    #[serde(default)]
+    pub summon: Option<(RunnerKind, usize)>,
+    /// For how long the boss can't be debuffed.
+    #[serde(default)]
+    pub immunity: f32,
+    /// Abilities the boss gains.
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+    /// The sprite the boss is drawn with from then on.
+    #[serde(default)]
+    pub sprite: Option<usize>,
+}
+
+fn unchanged_speed() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RunnerKind {
+    Grunt,
+    Flier,
+    Healer,
+    Splitter,
+    Splitling,
+    Knight,
+    Rogue,
+    Warlord,
+}
+
+impl RunnerKind {
+    pub fn hp(&self) -> f32 {
+        match self {
+            RunnerKind::Grunt => 100.0,
+            RunnerKind::Flier => 60.0,
+            RunnerKind::Healer => 80.0,
+            RunnerKind::Splitter => 120.0,
+            RunnerKind::Splitling => 30.0,
+            RunnerKind::Knight => 100.0,
+            RunnerKind::Rogue => 70.0,
+            RunnerKind::Warlord => 2000.0,
+        }
+    }
+
+    pub fn speed(&self) -> f32 {
+        match self {
+            RunnerKind::Grunt => 32.0,
+            RunnerKind::Flier => 24.0,
+            RunnerKind::Healer => 28.0,
+            RunnerKind::Splitter => 24.0,
+            RunnerKind::Splitling => 40.0,
+            RunnerKind::Knight => 24.0,
+            RunnerKind::Rogue => 36.0,
+            RunnerKind::Warlord => 16.0,
+        }
+    }
+
+    pub fn bounty(&self) -> usize {
+        match self {
+            RunnerKind::Grunt => 50,
+            RunnerKind::Flier => 40,
+            RunnerKind::Healer => 60,
+            RunnerKind::Splitter => 40,
+            RunnerKind::Splitling => 10,
+            RunnerKind::Knight => 60,
+            RunnerKind::Rogue => 60,
+            RunnerKind::Warlord => 500,
+        }
+    }
+
+    pub fn sprite_number(&self) -> usize {
+        match self {
+            RunnerKind::Grunt => 125,
+            RunnerKind::Flier => 132,
+            RunnerKind::Healer => 118,
+            RunnerKind::Splitter => 111,
+            RunnerKind::Splitling => 112,
+            RunnerKind::Knight => 119,
+            RunnerKind::Rogue => 126,
+            RunnerKind::Warlord => 139,
+        }
+    }
+
+    pub fn layer(&self) -> Layer {
+        match self {
+            RunnerKind::Flier => Layer::Air,
+            _ => Layer::Ground,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunnerKind::Grunt => "Grunt",
+            RunnerKind::Flier => "Flier",
+            RunnerKind::Healer => "Healer",
+            RunnerKind::Splitter => "Splitter",
+            RunnerKind::Splitling => "Splitling",
+            RunnerKind::Knight => "Knight",
+            RunnerKind::Rogue => "Rogue",
+            RunnerKind::Warlord => "Warlord",
+        }
+    }
+}
+
+impl Default for RunnerKind {
+    fn default() -> Self {
+        RunnerKind::Grunt
+    }
+}
+
+/// A temporary change to a runner, e.g. the slow of a Frost tower.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Debuff {
+    /// By how much the speed of the runner is multiplied.
+    speed: f32,
+    duration: f32,
+}
+
+impl Debuff {
+    pub fn new(speed: f32, duration: f32) -> Self {
+        Self { speed, duration }
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn tick(&mut self, duration: f32) {
+        self.duration -= duration;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Runner {
+    id: Id,
+    kind: RunnerKind,
+    // the wave which brought the runner (or whoever it came from) in
+    wave: usize,
+    road: usize,
+    pos: usize,
+    position: Point,
+    hp: f32,
+    max_hp: f32,
+    shield: f32,
+    speed: f32,
+    abilities: Vec<Ability>,
+    debuffs: Vec<Debuff>,
+    bounty: usize,
+    sprite: usize,
+    // the phases of a boss, and the next one
+    phases: Vec<Phase>,
+    phase: usize,
+    // how long until debuffs can be applied again
+    immunity: f32,
+}
+
+impl Runner {
+    /// Creates a runner at `position`, heading to waypoint `pos + 1` of path
+    /// `road`. Depending on the layer of the runner, `road` is either a road
+    /// or a flight path. Its abilities and phases come from `archetype`.
+    pub fn new(
+        id: Id,
+        kind: RunnerKind,
+        archetype: &Archetype,
+        wave: usize,
+        road: usize,
+        pos: usize,
+        position: Point,
+    ) -> Self {
+        let shield = archetype
+            .abilities()
+            .iter()
+            .map(|a| match a {
+                Ability::Shield { amount } => *amount,
+                _ => 0.0,
+            })
+            .sum();
+        Self {
+            id,
+            kind,
+            wave,
+            road,
+            pos,
+            position,
+            hp: kind.hp(),
+            max_hp: kind.hp(),
+            shield,
+            speed: kind.speed(),
+            abilities: archetype.abilities().clone(),
+            debuffs: vec![],
+            bounty: kind.bounty(),
+            sprite: kind.sprite_number(),
+            phases: archetype.phases().clone(),
+            phase: 0,
+            immunity: 0.0,
+        }
+    }
+
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    pub fn kind(&self) -> RunnerKind {
+        self.kind
+    }
+
+    /// The wave the runner belongs to.
+    pub fn wave(&self) -> usize {
+        self.wave
+    }
+
+    pub fn layer(&self) -> Layer {
+        self.kind.layer()
+    }
+
+    pub fn road(&self) -> usize {
+        self.road
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn set_pos(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    /// Where the runner is in world coordinates.
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn position_mut(&mut self) -> &mut Point {
+        &mut self.position
+    }
+
+    pub fn hp(&self) -> f32 {
+        self.hp
+    }
+
+    pub fn max_hp(&self) -> f32 {
+        self.max_hp
+    }
+
+    /// Deals damage to the runner, which its shield absorbs first.
+    pub fn deal_damage(&mut self, damage: f32) {
+        let absorbed = damage.min(self.shield);
+        self.shield -= absorbed;
+        self.hp -= damage - absorbed;
+    }
+
+    /// Restores hp, up to the max hp of the runner.
+    pub fn heal(&mut self, amount: f32) {
+        self.hp = (self.hp + amount).min(self.max_hp);
+    }
+
+    pub fn shield(&self) -> f32 {
+        self.shield
+    }
+
+    pub fn abilities(&self) -> &Vec<Ability> {
+        &self.abilities
+    }
+
+    /// The sprite the runner is drawn with, which the phases of a boss can
+    /// change.
+    pub fn sprite_number(&self) -> usize {
+        self.sprite
+    }
+
+    pub fn is_boss(&self) -> bool {
+        !self.phases.is_empty()
+    }
+
+    pub fn is_stealthy(&self) -> bool {
+        self.abilities().contains(&Ability::Stealth)
+    }
+
+    /// The speed of the runner, slowed down by its debuffs.
+    pub fn speed(&self) -> f32 {
+        self.debuffs
+            .iter()
+            .fold(self.speed, |speed, d| speed * d.speed())
+    }
+
+    pub fn debuffs(&self) -> &Vec<Debuff> {
+        &self.debuffs
+    }
+
+    pub fn apply_debuff(&mut self, debuff: Option<Debuff>) {
+        if self.immunity > 0. {
+            return;
+        }
+        if let Some(debuff) = debuff {
+            self.debuffs.push(debuff);
+        }
+    }
+
+    pub fn is_immune(&self) -> bool {
+        self.immunity > 0.
+    }
+
+    /// Starts the next phase of a boss if its hp dropped low enough, and
+    /// returns it.
+    pub fn advance_phase(&mut self) -> Option<Phase> {
+        let phase = self.phases.get(self.phase)?.clone();
+        if self.hp > phase.hp * self.max_hp {
+            return None;
+        }
+        self.phase += 1;
+        self.speed *= phase.speed;
+        if phase.immunity > 0. {
+            self.immunity = phase.immunity;
+            self.debuffs.clear();
+        }
+        for &ability in phase.abilities.iter() {
+            if let Ability::Shield { amount } = ability {
+                self.shield += amount;
+            }
+            self.abilities.push(ability);
+        }
+        if let Some(sprite) = phase.sprite {
+            self.sprite = sprite;
+        }
+        Some(phase)
+    }
+
+    pub fn tick(&mut self, duration: f32) {
+        self.immunity -= duration;
+        for debuff in self.debuffs.iter_mut() {
+            debuff.tick(duration);
+        }
+        self.debuffs.retain(|d| d.duration() > 0.);
+    }
+
+    pub fn bounty(&self) -> usize {
+        self.bounty
+    }
+}
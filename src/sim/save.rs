@@ -0,0 +1,95 @@
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::sim::{Level, Simulation};
+
+/// The version of the save format. Saves of any other version can't be
+/// loaded, so it has to go up whenever the format changes.
+pub const SAVE_VERSION: u32 = 8;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    /// The save was made by a different version of the game.
+    Version(u32),
+    Format(ron::de::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Version(version) => write!(
+                f,
+                "The save is of version {}, but only version {} is supported",
+                version, SAVE_VERSION
+            ),
+            SaveError::Format(e) => write!(f, "The save is corrupted: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+/// A game which can be continued later: the level it is played on and
+/// everything that happened on it so far.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    version: u32,
+    level: PathBuf,
+    sim: Simulation,
+}
+
+impl SaveGame {
+    pub fn new(level: PathBuf, sim: &Simulation) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            level,
+            sim: sim.clone(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        let save = std::fs::read_to_string(path)?;
+        match version(&save) {
+            Some(version) if version != SAVE_VERSION => Err(SaveError::Version(version)),
+            _ => ron::de::from_str(&save).map_err(SaveError::Format),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let save = ron::ser::to_string_pretty(self, PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        std::fs::write(path, save)
+    }
+
+    pub fn level(&self) -> &Path {
+        &self.level
+    }
+
+    /// The saved game, continuing on `level` (which saves don't keep).
+    pub fn into_simulation(self, level: Level) -> Simulation {
+        self.sim.with_level(level)
+    }
+}
+
+/// Finds the version of a save without parsing the rest of it, as saves of
+/// other versions may not parse at all.
+fn version(save: &str) -> Option<u32> {
+    let start = save.find("version:")? + "version:".len();
+    save[start..]
+        .trim_start()
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,604 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use std::{collections::VecDeque, fmt};
+
+use crate::sim::{
+    Ability, Archetypes, Coord, Id, Level, Missle, Phase, Point, Runner, RunnerKind, Stats,
+    Targeting, Tower, TowerKind, Waves, MISSLE_SPEED,
+};
+
+/// How long a single step of the simulation is, in seconds.
+pub const DT: f32 = 1.0 / 60.0;
+
+/// How many times faster than normal the game can be played.
+pub const MAX_SPEED: u32 = 3;
+
+/// The points earned for each second a wave is called before it would have
+/// started.
+pub const EARLY_CALL_SCORE: f32 = 2.0;
+
+/// How many ticks killed runners are remembered for, so that the game can
+/// tell them apart from the ones which reached an exit.
+const KILLED_MEMORY: u64 = 60;
+
+/// Everything the player can do to change the course of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Command {
+    /// Builds a tower on an empty build point.
+    Build { pos: Coord, kind: TowerKind },
+    /// Replaces a tower with one of its upgrades.
+    Upgrade { pos: Coord, kind: TowerKind },
+    /// Removes a tower, giving back some of its cost.
+    Sell { pos: Coord },
+    /// Changes which runners a tower shoots at.
+    SetTargeting { pos: Coord, targeting: Targeting },
+    /// Changes how many steps are made for each `DT` seconds of real time,
+    /// 0 pausing the game. This doesn't change the outcome of the game.
+    SetSpeed { speed: u32 },
+    /// Starts the next wave right away instead of waiting for its delay.
+    NextWave,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError {
+    NotEnoughGold,
+    /// There's nothing the command can be applied to at that position.
+    InvalidTarget,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NotEnoughGold => write!(f, "Not enough resources!"),
+            CommandError::InvalidTarget => write!(f, "Can't do that here!"),
+        }
+    }
+}
+
+/// The state of a game. Given the same level, waves, seed and commands (applied
+/// at the same ticks), two simulations always end up in the same state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Simulation {
+    // saves only keep the path of the level, see `SaveGame`
+    #[serde(skip)]
+    level: Level,
+    waves: Waves,
+    archetypes: Archetypes,
+    runners: Vec<Runner>,
+    towers: Vec<Tower>,
+    missles: Vec<Missle>,
+    gold: usize,
+    lives: usize,
+    // the bounty of every runner killed, and points for calling waves early
+    score: usize,
+    next_id: Id,
+    tick: u64,
+    seed: u64,
+    // the only source of randomness the simulation is allowed to use
+    rng: Pcg32,
+    speed: u32,
+    // the commands which were applied, and the tick they were applied at
+    history: Vec<(u64, Command)>,
+    // commands of a replay which still have to be applied
+    script: VecDeque<(u64, Command)>,
+    // the tick a command of the script failed at (replays aren't saved)
+    #[serde(skip)]
+    desynced: Option<u64>,
+    stats: Stats,
+    // runners killed lately, and the tick they died at
+    #[serde(skip)]
+    killed: VecDeque<(u64, Id)>,
+}
+
+impl Simulation {
+    pub fn new(level: Level, waves: Waves, seed: u64) -> Self {
+        Self {
+            level,
+            waves,
+            archetypes: Default::default(),
+            runners: vec![],
+            towers: vec![],
+            missles: vec![],
+            gold: 100,
+            lives: 20,
+            score: 0,
+            next_id: 0,
+            tick: 0,
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+            speed: 1,
+            history: vec![],
+            script: Default::default(),
+            desynced: None,
+            stats: Default::default(),
+            killed: Default::default(),
+        }
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Puts the simulation on `level`, e.g. after it was loaded from a save.
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Gives the runners which come in from now on the abilities of
+    /// `archetypes`, instead of those the game comes with.
+    pub fn with_archetypes(mut self, archetypes: Archetypes) -> Self {
+        self.archetypes = archetypes;
+        self
+    }
+
+    pub fn archetypes(&self) -> &Archetypes {
+        &self.archetypes
+    }
+
+    pub fn waves(&self) -> &Waves {
+        &self.waves
+    }
+
+    pub fn runners(&self) -> &Vec<Runner> {
+        &self.runners
+    }
+
+    pub fn runner(&self, id: Id) -> Option<&Runner> {
+        self.runners.iter().find(|r| r.id() == id)
+    }
+
+    pub fn towers(&self) -> &Vec<Tower> {
+        &self.towers
+    }
+
+    pub fn tower_at(&self, pos: Coord) -> Option<&Tower> {
+        self.towers.iter().find(|t| t.pos() == pos)
+    }
+
+    pub fn missles(&self) -> &Vec<Missle> {
+        &self.missles
+    }
+
+    pub fn gold(&self) -> usize {
+        self.gold
+    }
+
+    pub fn lives(&self) -> usize {
+        self.lives
+    }
+
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    /// How many steps the simulation has made so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The seed the simulation's random number generator started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Whether the runner with that id was killed during the last second,
+    /// rather than leaving through an exit.
+    pub fn was_killed(&self, id: Id) -> bool {
+        self.killed.iter().any(|&(_, killed)| killed == id)
+    }
+
+    /// What happened during the game so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// How many steps should be made for each `DT` seconds of real time.
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /// The commands which were applied so far, and the tick each of them was
+    /// applied at.
+    pub fn history(&self) -> &Vec<(u64, Command)> {
+        &self.history
+    }
+
+    /// Makes the simulation apply `commands` by itself, each of them right
+    /// before the step following its tick, e.g. to play back a replay.
+    pub fn play(&mut self, commands: Vec<(u64, Command)>) {
+        self.script = commands.into_iter().collect();
+    }
+
+    /// The tick at which a command given to `play` couldn't be applied, if
+    /// any. The game then went another way than when the commands were
+    /// recorded.
+    pub fn desynced(&self) -> Option<u64> {
+        self.desynced
+    }
+
+    /// A hash of the state of the game. Two simulations which went the same
+    /// way have the same checksum.
+    pub fn checksum(&self) -> u64 {
+        let mut hash = Fnv::new();
+        hash.write(self.tick);
+        hash.write(self.gold as u64);
+        hash.write(self.lives as u64);
+        hash.write(self.score as u64);
+        hash.write(self.next_id as u64);
+        for runner in self.runners.iter() {
+            hash.write(runner.id() as u64);
+            hash.write(runner.pos() as u64);
+            hash.write(runner.hp().to_bits() as u64);
+            hash.write(runner.position().x.to_bits() as u64);
+            hash.write(runner.position().y.to_bits() as u64);
+        }
+        for tower in self.towers.iter() {
+            hash.write(tower.id() as u64);
+            hash.write(tower.kind() as u64);
+            hash.write(tower.cd().to_bits() as u64);
+        }
+        for missle in self.missles.iter() {
+            hash.write(missle.id() as u64);
+            hash.write(missle.position().x.to_bits() as u64);
+            hash.write(missle.position().y.to_bits() as u64);
+        }
+        hash.finish()
+    }
+
+    /// Whether the player ran out of lives.
+    pub fn is_lost(&self) -> bool {
+        self.lives == 0
+    }
+
+    /// Whether all waves have been beaten.
+    pub fn is_won(&self) -> bool {
+        !self.is_lost() && self.waves.is_done() && self.runners.is_empty()
+    }
+
+    pub fn apply(&mut self, command: Command) -> Result<(), CommandError> {
+        self.execute(command)?;
+        self.history.push((self.tick, command));
+        Ok(())
+    }
+
+    fn execute(&mut self, command: Command) -> Result<(), CommandError> {
+        match command {
+            Command::Build { pos, kind } => {
+                if !self.level.build_points().contains(&pos)
+                    || self.tower_at(pos).is_some()
+                    || !TowerKind::buildable().contains(&kind)
+                {
+                    return Err(CommandError::InvalidTarget);
+                }
+                self.buy(kind)?;
+                let id = self.next_id();
+                self.towers.push(Tower::new(id, kind, pos));
+            }
+            Command::Upgrade { pos, kind } => {
+                let index = self
+                    .towers
+                    .iter()
+                    .position(|t| t.pos() == pos && t.upgrades().contains(&kind))
+                    .ok_or(CommandError::InvalidTarget)?;
+                self.buy(kind)?;
+                // the upgrade is a brand new tower which aims like the old one
+                let id = self.next_id();
+                let mut tower = Tower::new(id, kind, pos);
+                tower.set_targeting(self.towers[index].targeting());
+                tower.keep_record(&self.towers[index]);
+                self.towers[index] = tower;
+            }
+            Command::Sell { pos } => {
+                let index = self
+                    .towers
+                    .iter()
+                    .position(|t| t.pos() == pos)
+                    .ok_or(CommandError::InvalidTarget)?;
+                let tower = self.towers.remove(index);
+                self.gold += tower.refund();
+                self.stats.add_sold(tower);
+            }
+            Command::SetTargeting { pos, targeting } => {
+                self.towers
+                    .iter_mut()
+                    .find(|t| t.pos() == pos)
+                    .ok_or(CommandError::InvalidTarget)?
+                    .set_targeting(targeting);
+            }
+            Command::SetSpeed { speed } => {
+                if speed > MAX_SPEED {
+                    return Err(CommandError::InvalidTarget);
+                }
+                self.speed = speed;
+            }
+            Command::NextWave => {
+                let skipped = self.waves.time_until_next().unwrap_or(0.0);
+                if !self.waves.skip_delay() {
+                    return Err(CommandError::InvalidTarget);
+                }
+                self.score += (skipped * EARLY_CALL_SCORE) as usize;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends in a runner of `kind` outside of the waves, from `spawn` (or any
+    /// spawn if not given). Returns its id, unless no path of its layer starts
+    /// at the spawn.
+    pub fn spawn(&mut self, kind: RunnerKind, spawn: Option<usize>) -> Option<Id> {
+        let wave = self.waves.current();
+        self.spawn_runner(kind, spawn, wave)
+    }
+
+    /// Advances the game by `DT` seconds.
+    pub fn step(&mut self) {
+        let dt = DT;
+        while self
+            .script
+            .front()
+            .map_or(false, |(tick, _)| *tick <= self.tick)
+        {
+            let (_, command) = self.script.pop_front().unwrap();
+            // it worked when it was recorded, so it can only fail if we desynced
+            if self.apply(command).is_err() && self.desynced.is_none() {
+                self.desynced = Some(self.tick);
+            }
+        }
+        self.tick += 1;
+        while self
+            .killed
+            .front()
+            .map_or(false, |(tick, _)| tick + KILLED_MEMORY < self.tick)
+        {
+            self.killed.pop_front();
+        }
+        self.spawn_runners(dt);
+        self.move_runners(dt);
+        self.use_abilities(dt);
+        self.advance_phases();
+        self.fire_towers(dt);
+        self.move_missles(dt);
+    }
+
+    fn next_id(&mut self) -> Id {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn buy(&mut self, kind: TowerKind) -> Result<(), CommandError> {
+        if kind.cost() > self.gold {
+            return Err(CommandError::NotEnoughGold);
+        }
+        self.gold -= kind.cost();
+        Ok(())
+    }
+
+    /// Adds `count` runners of `kind` at `position`, heading to waypoint
+    /// `pos + 1` of `road`.
+    fn add_runners(
+        &mut self,
+        kind: RunnerKind,
+        count: usize,
+        wave: usize,
+        road: usize,
+        pos: usize,
+        position: Point,
+    ) {
+        for _ in 0..count {
+            let id = self.next_id();
+            let archetype = self.archetypes.get(kind);
+            self.runners
+                .push(Runner::new(id, kind, archetype, wave, road, pos, position));
+        }
+    }
+
+    fn spawn_runners(&mut self, dt: f32) {
+        let wave = self.waves.current();
+        for (kind, spawn) in self.waves.tick(dt) {
+            self.spawn_runner(kind, spawn, wave);
+        }
+    }
+
+    fn spawn_runner(&mut self, kind: RunnerKind, spawn: Option<usize>, wave: usize) -> Option<Id> {
+        let spawns = self.level.spawns();
+        // runners of a group without a spawn can come from anywhere
+        let spawn = match spawn {
+            Some(spawn) => spawn,
+            None => spawns[self.rng.gen_range(0, spawns.len())].id(),
+        };
+        let paths = self.level.paths_from(kind.layer(), spawn);
+        if paths.is_empty() {
+            return None;
+        }
+        let road = paths[self.rng.gen_range(0, paths.len())];
+        let position = self.level.waypoint(kind.layer(), road, 0).unwrap();
+        self.add_runners(kind, 1, wave, road, 0, position);
+        // the runner we just added
+        Some(self.next_id - 1)
+    }
+
+    fn move_runners(&mut self, dt: f32) {
+        let level = &self.level;
+        let stats = &mut self.stats;
+        let mut leaked = 0;
+        let mut arrived = vec![];
+        for runner in self.runners.iter_mut() {
+            runner.tick(dt);
+            let next_pos = level.waypoint(runner.layer(), runner.road(), runner.pos() + 1);
+            if let Some(target) = next_pos {
+                if runner.position().distance(&target) <= 1.0 {
+                    runner.set_pos(runner.pos() + 1);
+                } else {
+                    let step = dt * runner.speed();
+                    runner.position_mut().step_towards(&target, step);
+                }
+            } else {
+                // we have reached the end!
+                let exit = level.path_exit(runner.layer(), runner.road());
+                let penalty = level.exit(exit).map_or(1, |e| e.penalty());
+                stats.add_leak(runner.wave());
+                leaked += penalty;
+                arrived.push(runner.id());
+            }
+        }
+        self.runners.retain(|r| !arrived.contains(&r.id()));
+        self.lives = self.lives.saturating_sub(leaked);
+    }
+
+    fn use_abilities(&mut self, dt: f32) {
+        let mut heals = vec![];
+        for runner in self.runners.iter() {
+            for &ability in runner.abilities() {
+                if let Ability::Heal { radius, amount } = ability {
+                    heals.push((runner.id(), runner.position(), radius, amount * dt));
+                }
+            }
+        }
+        for (healer, origin, radius, amount) in heals {
+            for runner in self.runners.iter_mut() {
+                // healers can't heal themselves
+                if runner.id() != healer && origin.distance(&runner.position()) <= radius {
+                    runner.heal(amount);
+                }
+            }
+        }
+    }
+
+    fn advance_phases(&mut self) {
+        let mut summons = vec![];
+        for runner in self.runners.iter_mut() {
+            if let Some(Phase {
+                summon: Some((kind, count)),
+                ..
+            }) = runner.advance_phase()
+            {
+                summons.push((
+                    kind,
+                    count,
+                    runner.wave(),
+                    runner.road(),
+                    runner.pos(),
+                    runner.position(),
+                ));
+            }
+        }
+        // minions show up right where the boss is
+        for (kind, count, wave, road, pos, position) in summons {
+            self.add_runners(kind, count, wave, road, pos, position);
+        }
+    }
+
+    fn fire_towers(&mut self, dt: f32) {
+        for tower in self.towers.iter_mut() {
+            tower.tick(dt);
+            if tower.cd() > 0. {
+                continue;
+            }
+            let origin = self.level.center(tower.pos());
+            let in_range = self.runners.iter().filter(|r| {
+                tower.can_target(r) && origin.distance(&r.position()) <= tower.radius()
+            });
+            let target = match tower.targeting() {
+                Targeting::First => in_range.max_by_key(|r| r.pos()),
+                Targeting::Last => in_range.min_by_key(|r| r.pos()),
+                Targeting::Strongest => {
+                    in_range.max_by(|a, b| a.hp().partial_cmp(&b.hp()).unwrap())
+                }
+                Targeting::Weakest => in_range.min_by(|a, b| a.hp().partial_cmp(&b.hp()).unwrap()),
+            };
+            if let Some(target) = target {
+                let id = self.next_id;
+                self.next_id += 1;
+                self.missles.push(Missle::new(
+                    id,
+                    tower.id(),
+                    target.id(),
+                    origin,
+                    tower.damage(),
+                    tower.debuff(),
+                ));
+                tower.reset_cd();
+            }
+        }
+    }
+
+    fn move_missles(&mut self, dt: f32) {
+        let mut gone = vec![];
+        for missle in self.missles.iter_mut() {
+            let runner = match self.runners.iter_mut().find(|r| r.id() == missle.target()) {
+                Some(runner) => runner,
+                None => {
+                    // our missle's target is gone, well we are going away as well
+                    gone.push(missle.id());
+                    continue;
+                }
+            };
+            if missle.position().distance(&runner.position()) <= 8.0 {
+                let hp = runner.hp();
+                runner.deal_damage(missle.damage());
+                runner.apply_debuff(missle.debuff());
+                // towers count the hp they took, which runners which are
+                // already dead don't have
+                if let Some(tower) = self.towers.iter_mut().find(|t| t.id() == missle.tower()) {
+                    if hp > 0.0 {
+                        tower.record_hit(hp - runner.hp().max(0.0), runner.hp() <= 0.0);
+                    }
+                }
+                gone.push(missle.id());
+            } else {
+                missle
+                    .position_mut()
+                    .step_towards(&runner.position(), dt * MISSLE_SPEED);
+            }
+        }
+        self.missles.retain(|m| !gone.contains(&m.id()));
+
+        let mut splits = vec![];
+        for runner in self.runners.iter().filter(|r| r.hp() <= 0.0) {
+            self.gold += runner.bounty();
+            self.score += runner.bounty();
+            self.killed.push_back((self.tick, runner.id()));
+            for &ability in runner.abilities() {
+                if let Ability::Split { kind, count } = ability {
+                    splits.push((
+                        kind,
+                        count,
+                        runner.wave(),
+                        runner.road(),
+                        runner.pos(),
+                        runner.position(),
+                    ));
+                }
+            }
+        }
+        self.runners.retain(|r| r.hp() > 0.0);
+        // splitters leave their offspring where they died
+        for (kind, count, wave, road, pos, position) in splits {
+            self.add_runners(kind, count, wave, road, pos, position);
+        }
+    }
+}
+
+/// 64 bit FNV-1a, which unlike `DefaultHasher` is the same everywhere.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, value: u64) {
+        for byte in value.to_le_bytes().iter() {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sim::Tower;
+
+/// What happened during a game, e.g. to tell how well a level is balanced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    // runners of each wave which reached an exit
+    leaked: Vec<usize>,
+    // towers which were sold, as they were when it happened
+    sold: Vec<Tower>,
+}
+
+impl Stats {
+    /// How many runners of `wave` reached an exit.
+    pub fn leaked(&self, wave: usize) -> usize {
+        self.leaked.get(wave).cloned().unwrap_or(0)
+    }
+
+    pub fn add_leak(&mut self, wave: usize) {
+        if self.leaked.len() <= wave {
+            self.leaked.resize(wave + 1, 0);
+        }
+        self.leaked[wave] += 1;
+    }
+
+    /// The towers which were sold, with what they did before.
+    pub fn sold(&self) -> &Vec<Tower> {
+        &self.sold
+    }
+
+    pub fn add_sold(&mut self, tower: Tower) {
+        self.sold.push(tower);
+    }
+}
@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sim::{Coord, Debuff, Id, Layer, Point, Runner};
+
+pub const MISSLE_SPEED: f32 = 64.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum TowerKind {
+    Simple,
+    Turret,
+    Frost,
+}
+
+impl TowerKind {
+    /// The towers which can be built on an empty build point.
+    pub const fn buildable() -> [TowerKind; 2] {
+        [TowerKind::Simple, TowerKind::Frost]
+    }
+
+    pub fn radius(&self) -> f32 {
+        match self {
+            TowerKind::Simple => 30.0,
+            TowerKind::Turret => 30.0,
+            TowerKind::Frost => 20.0,
+        }
+    }
+
+    pub fn damage(&self) -> f32 {
+        match self {
+            TowerKind::Simple => 20.0,
+            TowerKind::Turret => 15.0,
+            TowerKind::Frost => 10.0,
+        }
+    }
+
+    pub fn sprite_number(&self) -> usize {
+        match self {
+            TowerKind::Simple => 22,
+            TowerKind::Turret => 23,
+            TowerKind::Frost => 24,
+        }
+    }
+
+    pub fn cost(&self) -> usize {
+        match self {
+            TowerKind::Simple => 50,
+            TowerKind::Turret => 100,
+            TowerKind::Frost => 75,
+        }
+    }
+
+    /// How many seconds the tower waits between two missles.
+    pub fn speed(&self) -> f32 {
+        match self {
+            TowerKind::Simple => 1.0,
+            TowerKind::Turret => 0.4,
+            TowerKind::Frost => 1.0,
+        }
+    }
+
+    /// The damage the tower deals per second when it always has a target.
+    pub fn dps(&self) -> f32 {
+        self.damage() / self.speed()
+    }
+
+    /// Whether the tower can shoot at runners on `layer`.
+    pub fn can_target(&self, layer: Layer) -> bool {
+        match (self, layer) {
+            (TowerKind::Frost, Layer::Air) => false,
+            _ => true,
+        }
+    }
+
+    /// Whether the tower can see (and shoot at) stealthy runners.
+    pub fn detects_stealth(&self) -> bool {
+        match self {
+            TowerKind::Turret => true,
+            _ => false,
+        }
+    }
+
+    pub fn upgrades(&self) -> Vec<TowerKind> {
+        match self {
+            TowerKind::Simple => vec![TowerKind::Turret],
+            TowerKind::Turret => vec![],
+            TowerKind::Frost => vec![],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TowerKind::Simple => "Simple tower",
+            TowerKind::Turret => "Turret",
+            TowerKind::Frost => "Frost tower",
+        }
+    }
+}
+
+/// Which of the runners in range a tower shoots at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Targeting {
+    /// The runner which is closest to its exit.
+    First,
+    /// The runner which is furthest from its exit.
+    Last,
+    Strongest,
+    Weakest,
+}
+
+impl Targeting {
+    /// The targeting which comes after this one, wrapping around.
+    pub fn next(&self) -> Self {
+        match self {
+            Targeting::First => Targeting::Last,
+            Targeting::Last => Targeting::Strongest,
+            Targeting::Strongest => Targeting::Weakest,
+            Targeting::Weakest => Targeting::First,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Targeting::First => "first",
+            Targeting::Last => "last",
+            Targeting::Strongest => "strongest",
+            Targeting::Weakest => "weakest",
+        }
+    }
+}
+
+impl Default for Targeting {
+    fn default() -> Self {
+        Targeting::First
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tower {
+    id: Id,
+    kind: TowerKind,
+    pos: Coord,
+    cd: f32,
+    targeting: Targeting,
+    // what the tower did so far
+    damage_dealt: f32,
+    kills: usize,
+}
+
+impl Tower {
+    pub fn new(id: Id, kind: TowerKind, pos: Coord) -> Tower {
+        Tower {
+            id,
+            kind,
+            pos,
+            cd: 0.,
+            targeting: Default::default(),
+            damage_dealt: 0.,
+            kills: 0,
+        }
+    }
+
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.kind.radius()
+    }
+
+    pub fn damage(&self) -> f32 {
+        self.kind.damage()
+    }
+
+    pub fn kind(&self) -> TowerKind {
+        self.kind
+    }
+
+    pub fn pos(&self) -> Coord {
+        self.pos
+    }
+
+    /// Whether the tower can shoot at `runner`.
+    pub fn can_target(&self, runner: &Runner) -> bool {
+        self.kind.can_target(runner.layer())
+            && (!runner.is_stealthy() || self.kind.detects_stealth())
+    }
+
+    pub fn cd(&self) -> f32 {
+        self.cd
+    }
+
+    pub fn targeting(&self) -> Targeting {
+        self.targeting
+    }
+
+    pub fn set_targeting(&mut self, targeting: Targeting) {
+        self.targeting = targeting;
+    }
+
+    /// How much hp the missles of the tower took from runners.
+    pub fn damage_dealt(&self) -> f32 {
+        self.damage_dealt
+    }
+
+    /// How many runners the missles of the tower finished off.
+    pub fn kills(&self) -> usize {
+        self.kills
+    }
+
+    /// Records a hit of one of the tower's missles.
+    pub fn record_hit(&mut self, damage: f32, killed: bool) {
+        self.damage_dealt += damage;
+        if killed {
+            self.kills += 1;
+        }
+    }
+
+    /// Makes the tower keep what `old` did, e.g. when it's an upgrade of it.
+    pub fn keep_record(&mut self, old: &Tower) {
+        self.damage_dealt = old.damage_dealt;
+        self.kills = old.kills;
+    }
+
+    /// How much gold selling the tower gives back.
+    pub fn refund(&self) -> usize {
+        self.kind.cost() / 2
+    }
+
+    pub fn reset_cd(&mut self) {
+        self.cd = self.kind.speed();
+    }
+
+    pub fn sprite_number(&self) -> usize {
+        self.kind.sprite_number()
+    }
+
+    pub fn debuff(&self) -> Option<Debuff> {
+        if let TowerKind::Simple = self.kind {
+            None
+        } else {
+            Some(Debuff::new(0.5, 1.0))
+        }
+    }
+
+    pub fn cost(&self) -> usize {
+        self.kind.cost()
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        self.cd -= delta;
+    }
+
+    pub fn upgrades(&self) -> Vec<TowerKind> {
+        self.kind.upgrades()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Missle {
+    id: Id,
+    // the tower which fired the missle
+    tower: Id,
+    target: Id,
+    position: Point,
+    damage: f32,
+    debuff: Option<Debuff>,
+}
+
+impl Missle {
+    pub fn new(
+        id: Id,
+        tower: Id,
+        target: Id,
+        position: Point,
+        damage: f32,
+        debuff: Option<Debuff>,
+    ) -> Self {
+        Self {
+            id,
+            tower,
+            target,
+            position,
+            damage,
+            debuff,
+        }
+    }
+
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// The tower which fired the missle.
+    pub fn tower(&self) -> Id {
+        self.tower
+    }
+
+    pub fn target(&self) -> Id {
+        self.target
+    }
+
+    /// Where the missle is in world coordinates.
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn position_mut(&mut self) -> &mut Point {
+        &mut self.position
+    }
+
+    pub fn damage(&self) -> f32 {
+        self.damage
+    }
+
+    pub fn debuff(&self) -> Option<Debuff> {
+        self.debuff
+    }
+}
@@ -0,0 +1,311 @@
+use std::{collections::HashSet, fmt};
+
+use crate::sim::{gather_roads, tileset_tile, Coord, RunnerKind, Tiles};
+
+/// The tile properties the game knows about. `tower` and `runner` only label
+/// sprites of the tileset, `attack` and `death` name the tiles whose
+/// animation towers and runners play.
+const KNOWN_PROPERTIES: [&str; 15] = [
+    "road",
+    "up",
+    "right",
+    "down",
+    "left",
+    "construction-point",
+    "start-point",
+    "spawn-id",
+    "end-point",
+    "exit-id",
+    "leak-penalty",
+    "tower",
+    "runner",
+    "attack",
+    "death",
+];
+
+/// Something which keeps a Tiled map from being played. Positions are in
+/// tiles, counted from the top left corner like Tiled does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapError {
+    NoTileset,
+    /// Tilesets have to be made of a single image, collections of images
+    /// can't be drawn.
+    TilesetWithoutImage(String),
+    /// A tile whose gid doesn't belong to any tileset.
+    UnknownTile {
+        pos: Coord,
+        gid: u32,
+    },
+    NoStart,
+    NoEnd,
+    /// Several spawns have this id, e.g. because a start point tile with a
+    /// `spawn-id` is used more than once.
+    DuplicateSpawn(usize),
+    /// Several exits have this id.
+    DuplicateExit(usize),
+    /// A tile of a tileset has a property the game doesn't know.
+    UnknownProperty {
+        tileset: String,
+        tile: u32,
+        name: String,
+    },
+    RoadWithoutDirection(Coord),
+    /// No road leads from the spawn at this position to an exit.
+    NoPathToExit(Coord),
+    /// A road runners can take which doesn't lead to any exit.
+    DeadEnd(Coord),
+    /// A road which can't be reached from any spawn.
+    DisconnectedRoad(Coord),
+    /// A build point without any road next to it.
+    IsolatedBuildPoint(Coord),
+    /// A group of a wave, counted from 0, comes from a spawn the map doesn't
+    /// have.
+    UnknownSpawn {
+        wave: usize,
+        spawn: usize,
+    },
+    /// A group of a wave comes from a spawn with no path for its runners.
+    NoPathFromSpawn {
+        wave: usize,
+        spawn: usize,
+        runner: RunnerKind,
+    },
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::NoTileset => write!(f, "The map has no tileset"),
+            MapError::TilesetWithoutImage(name) => {
+                write!(f, "The tileset {} isn't made of a single image", name)
+            }
+            MapError::UnknownTile { pos, gid } => write!(
+                f,
+                "The tile at ({}, {}) has gid {}, which isn't in any tileset",
+                pos.x, pos.y, gid
+            ),
+            MapError::NoStart => write!(
+                f,
+                "The level has no spawn (a start-point tile or a spawn object)"
+            ),
+            MapError::NoEnd => write!(
+                f,
+                "The level has no exit (an end-point tile or an exit object)"
+            ),
+            MapError::DuplicateSpawn(id) => write!(f, "Several spawns have the id {}", id),
+            MapError::DuplicateExit(id) => write!(f, "Several exits have the id {}", id),
+            MapError::UnknownProperty {
+                tileset,
+                tile,
+                name,
+            } => write!(
+                f,
+                "Tile {} of the tileset {} has an unknown property \"{}\"",
+                tile, tileset, name
+            ),
+            MapError::RoadWithoutDirection(pos) => write!(
+                f,
+                "The road at ({}, {}) doesn't lead in any direction",
+                pos.x, pos.y
+            ),
+            MapError::NoPathToExit(pos) => write!(
+                f,
+                "No road leads from the spawn at ({}, {}) to an exit",
+                pos.x, pos.y
+            ),
+            MapError::DeadEnd(pos) => write!(
+                f,
+                "The road at ({}, {}) doesn't lead to an exit",
+                pos.x, pos.y
+            ),
+            MapError::DisconnectedRoad(pos) => write!(
+                f,
+                "The road at ({}, {}) can't be reached from a spawn",
+                pos.x, pos.y
+            ),
+            MapError::IsolatedBuildPoint(pos) => write!(
+                f,
+                "The construction point at ({}, {}) isn't next to a road",
+                pos.x, pos.y
+            ),
+            MapError::UnknownSpawn { wave, spawn } => write!(
+                f,
+                "Wave {} comes from spawn {}, which isn't on the map",
+                wave + 1,
+                spawn
+            ),
+            MapError::NoPathFromSpawn {
+                wave,
+                spawn,
+                runner,
+            } => write!(
+                f,
+                "Wave {} sends {}s from spawn {}, which they have no path from",
+                wave + 1,
+                runner.name(),
+                spawn
+            ),
+        }
+    }
+}
+
+/// Checks that a Tiled map makes a playable level, and returns everything
+/// which is wrong with it.
+pub fn validate_map(map: &tiled::Map) -> Vec<MapError> {
+    if map.tilesets.is_empty() {
+        return vec![MapError::NoTileset];
+    }
+    let mut errors = vec![];
+    for tileset in map.tilesets.iter() {
+        if tileset.images.len() != 1 {
+            errors.push(MapError::TilesetWithoutImage(tileset.name.clone()));
+        }
+        for tile in tileset.tiles.iter() {
+            let mut names: Vec<&String> = tile
+                .properties
+                .keys()
+                .filter(|name| !KNOWN_PROPERTIES.contains(&name.as_str()))
+                .collect();
+            names.sort();
+            for name in names {
+                errors.push(MapError::UnknownProperty {
+                    tileset: tileset.name.clone(),
+                    tile: tile.id,
+                    name: name.clone(),
+                });
+            }
+        }
+    }
+    for layer in map.layers.iter() {
+        for (y, row) in layer.tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if tile.gid != 0 && tileset_tile(map, tile.gid).is_none() {
+                    let pos = Coord::new(x, y);
+                    errors.push(MapError::UnknownTile { pos, gid: tile.gid });
+                }
+            }
+        }
+    }
+    // without the right tilesets the tiles can't be made sense of
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    let tiles = Tiles::read(map);
+    // our y axis points up, tiled's points down
+    let height = map.height as usize;
+    let flip = |pos: Coord| Coord::new(pos.x, height - 1 - pos.y);
+    if tiles.spawns.is_empty() {
+        errors.push(MapError::NoStart);
+    }
+    if tiles.exits.is_empty() {
+        errors.push(MapError::NoEnd);
+    }
+    let spawn_ids: Vec<usize> = tiles.spawns.iter().map(|s| s.id()).collect();
+    errors.extend(duplicates(&spawn_ids).map(MapError::DuplicateSpawn));
+    let exit_ids: Vec<usize> = tiles.exits.iter().map(|e| e.id()).collect();
+    errors.extend(duplicates(&exit_ids).map(MapError::DuplicateExit));
+    for &pos in tiles.roads.iter() {
+        if tiles.road_map[pos.x][pos.y] == 0 {
+            errors.push(MapError::RoadWithoutDirection(flip(pos)));
+        }
+    }
+    // roads drawn as polylines lead from their spawn to their exit
+    let object_roads = gather_roads(map, &tiles.spawns, &tiles.exits);
+    let object_road_tiles: HashSet<Coord> = object_roads
+        .iter()
+        .flat_map(|r| r.tiles().iter().cloned())
+        .collect();
+    for &pos in tiles.build_points.iter() {
+        let is_road = |n: &Coord| tiles.road_map[n.x][n.y] != 0 || object_road_tiles.contains(n);
+        if !neighbours(&tiles.road_map, pos).any(|n| is_road(&n)) {
+            errors.push(MapError::IsolatedBuildPoint(flip(pos)));
+        }
+    }
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    let exits: HashSet<Coord> = tiles.exits.iter().map(|e| e.pos()).collect();
+    // the tiles runners can get to from a spawn
+    let mut reachable = HashSet::new();
+    let mut todo: Vec<Coord> = tiles.spawns.iter().map(|s| s.pos()).collect();
+    while let Some(pos) = todo.pop() {
+        // runners stop at the first exit they reach
+        if reachable.insert(pos) && !exits.contains(&pos) {
+            todo.extend(moves(&tiles.road_map, pos));
+        }
+    }
+    // the tiles runners can get to an exit from
+    let mut leads_out = exits.clone();
+    let walkable: Vec<Coord> = tiles
+        .roads
+        .iter()
+        .cloned()
+        .chain(tiles.spawns.iter().map(|s| s.pos()))
+        .collect();
+    loop {
+        let before = leads_out.len();
+        for &pos in walkable.iter() {
+            if moves(&tiles.road_map, pos).any(|m| leads_out.contains(&m)) {
+                leads_out.insert(pos);
+            }
+        }
+        if leads_out.len() == before {
+            break;
+        }
+    }
+
+    for spawn in tiles.spawns.iter() {
+        let has_road = object_roads.iter().any(|r| r.spawn() == spawn.id());
+        if !has_road && !leads_out.contains(&spawn.pos()) {
+            errors.push(MapError::NoPathToExit(flip(spawn.pos())));
+        }
+    }
+    for &pos in tiles.roads.iter() {
+        if !reachable.contains(&pos) {
+            errors.push(MapError::DisconnectedRoad(flip(pos)));
+        } else if !leads_out.contains(&pos) {
+            errors.push(MapError::DeadEnd(flip(pos)));
+        }
+    }
+    errors
+}
+
+/// The ids found more than once, in the order they are first repeated.
+fn duplicates(ids: &[usize]) -> impl Iterator<Item = usize> + '_ {
+    ids.iter()
+        .enumerate()
+        .filter(move |&(i, id)| ids[..i].iter().filter(|&other| other == id).count() == 1)
+        .map(|(_, &id)| id)
+}
+
+/// The tiles runners can go to from `pos`. These don't have to be roads.
+fn moves(road_map: &[Vec<u8>], pos: Coord) -> impl Iterator<Item = Coord> + '_ {
+    [
+        (0, 1, 0b0001),
+        (1, 0, 0b0010),
+        (0, -1, 0b0100),
+        (-1, 0, 0b1000),
+    ]
+    .iter()
+    .filter(move |(_, _, dir)| road_map[pos.x][pos.y] & dir > 0)
+    .filter_map(move |(dx, dy, _)| offset(road_map, pos, *dx, *dy))
+}
+
+/// The eight tiles around `pos` which are on the map.
+fn neighbours(road_map: &[Vec<u8>], pos: Coord) -> impl Iterator<Item = Coord> + '_ {
+    (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+        .filter(|&offset| offset != (0, 0))
+        .filter_map(move |(dx, dy)| offset(road_map, pos, dx, dy))
+}
+
+fn offset(road_map: &[Vec<u8>], pos: Coord, dx: isize, dy: isize) -> Option<Coord> {
+    let (x, y) = (pos.x as isize + dx, pos.y as isize + dy);
+    if x < 0 || y < 0 || x as usize >= road_map.len() || y as usize >= road_map[0].len() {
+        None
+    } else {
+        Some(Coord::new(x as usize, y as usize))
+    }
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+use std::io;
+use std::path::Path;
+
+use crate::sim::{Level, LevelLoadError, MapError, RunnerKind};
+
+/// A number of runners entering the map one after the other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveGroup {
+    #[serde(default)]
+    runner: RunnerKind,
+    count: usize,
+    interval: f32,
+    /// The spawn which the runners come from (any spawn if not set).
+    #[serde(default)]
+    spawn: Option<usize>,
+}
+
+impl WaveGroup {
+    pub fn new(runner: RunnerKind, count: usize, interval: f32, spawn: Option<usize>) -> Self {
+        Self {
+            runner,
+            count,
+            interval,
+            spawn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wave {
+    /// How long to wait before the wave starts.
+    delay: f32,
+    /// All groups of a wave spawn at the same time.
+    groups: Vec<WaveGroup>,
+}
+
+impl Wave {
+    pub fn new(delay: f32, groups: Vec<WaveGroup>) -> Self {
+        Self { delay, groups }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Waves {
+    waves: Vec<Wave>,
+    current: usize,
+    timer: f32,
+    // how many runners each group of the current wave has spawned, and
+    // how long ago the last one was spawned
+    progress: Vec<(usize, f32)>,
+}
+
+impl Waves {
+    pub fn new(waves: Vec<Wave>) -> Self {
+        let mut waves = Self {
+            waves,
+            ..Default::default()
+        };
+        waves.start_wave();
+        waves
+    }
+
+    /// Loads the waves of a level from a RON file.
+    pub fn load(path: &Path) -> Result<Self, ron::de::Error> {
+        let file = std::fs::File::open(path)?;
+        let waves: Vec<Wave> = ron::de::from_reader(std::io::BufReader::new(file))?;
+        Ok(Self::new(waves))
+    }
+
+    /// The waves of the level at `path`, e.g. `level.ron` for `level.tmx`,
+    /// or generated ones if it doesn't come with its own.
+    pub fn for_level(path: &Path, level: &Level) -> Result<Self, LevelLoadError> {
+        let path = path.with_extension("ron");
+        // a broken file is an error, only a missing one means generated waves
+        let waves = match std::fs::File::open(&path) {
+            Ok(file) => {
+                let waves: Vec<Wave> = ron::de::from_reader(std::io::BufReader::new(file))
+                    .map_err(LevelLoadError::Waves)?;
+                Self::new(waves)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::generate(10),
+            Err(e) => return Err(e.into()),
+        };
+        let errors = waves.validate(level);
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+        Ok(waves)
+    }
+
+    /// Checks that the runners of each wave can enter `level`.
+    pub fn validate(&self, level: &Level) -> Vec<MapError> {
+        let mut errors = vec![];
+        for (wave, w) in self.waves.iter().enumerate() {
+            for group in w.groups.iter() {
+                let spawn = match group.spawn {
+                    Some(spawn) => spawn,
+                    None => continue,
+                };
+                if !level.spawns().iter().any(|s| s.id() == spawn) {
+                    errors.push(MapError::UnknownSpawn { wave, spawn });
+                } else if level.paths_from(group.runner.layer(), spawn).is_empty() {
+                    errors.push(MapError::NoPathFromSpawn {
+                        wave,
+                        spawn,
+                        runner: group.runner,
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Waves for levels which don't come with their own: each wave is
+    /// bigger than the previous one.
+    pub fn generate(count: usize) -> Self {
+        Self::new(
+            (0..count)
+                .map(|i| {
+                    Wave::new(
+                        5.0,
+                        vec![WaveGroup::new(RunnerKind::Grunt, 10 + i * 5, 1.0, None)],
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// The index of the wave currently being spawned.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn len(&self) -> usize {
+        self.waves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waves.is_empty()
+    }
+
+    /// Whether all waves have finished spawning.
+    pub fn is_done(&self) -> bool {
+        self.current >= self.waves.len()
+    }
+
+    /// How long before the current wave starts spawning, if it didn't start
+    /// yet.
+    pub fn time_until_next(&self) -> Option<f32> {
+        let wave = self.waves.get(self.current)?;
+        if self.timer < wave.delay {
+            Some(wave.delay - self.timer)
+        } else {
+            None
+        }
+    }
+
+    /// Makes the current wave start spawning right away. Returns false if it
+    /// already started, or if there are no waves left.
+    pub fn skip_delay(&mut self) -> bool {
+        match self.waves.get(self.current) {
+            Some(wave) if self.timer < wave.delay => {
+                self.timer = wave.delay;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Advances the current wave by `delta` seconds. Returns an entry for
+    /// each runner which has to be spawned, containing its kind and the
+    /// spawn it should come from.
+    pub fn tick(&mut self, delta: f32) -> Vec<(RunnerKind, Option<usize>)> {
+        let mut runners = vec![];
+        let wave = match self.waves.get(self.current) {
+            Some(wave) => wave,
+            None => return runners,
+        };
+        if self.timer < wave.delay {
+            self.timer += delta;
+            return runners;
+        }
+        for (group, (spawned, timer)) in wave.groups.iter().zip(self.progress.iter_mut()) {
+            if *spawned >= group.count {
+                continue;
+            }
+            *timer += delta;
+            if *timer >= group.interval {
+                *timer = 0.0;
+                *spawned += 1;
+                runners.push((group.runner, group.spawn));
+            }
+        }
+        let finished = wave
+            .groups
+            .iter()
+            .zip(self.progress.iter())
+            .all(|(group, (spawned, _))| *spawned >= group.count);
+        if finished {
+            self.current += 1;
+            self.start_wave();
+        }
+        runners
+    }
+
+    fn start_wave(&mut self) {
+        self.timer = 0.0;
+        self.progress = match self.waves.get(self.current) {
+            Some(wave) => vec![(0, 0.0); wave.groups.len()],
+            None => vec![],
+        };
+    }
+}
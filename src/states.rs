@@ -1,9 +1,9 @@
 use amethyst::{
     assets::Loader,
-    core::transform::Transform,
-    ecs::prelude::Entity,
+    core::{transform::Transform, Hidden},
+    ecs::prelude::{Entity, EntityBuilder},
     prelude::*,
-    renderer::{Camera, SpriteRender},
+    renderer::{palette::Srgba, resources::Tint, Camera, Transparent},
     ui::*,
 };
 
@@ -11,7 +11,15 @@ use std::{collections::HashMap, path::PathBuf};
 
 use super::Coord;
 use crate::{
+    controls::ControlsState,
+    hud,
     map::{self, Map},
+    render,
+    runner::{BossBar, BOSS_BAR_WIDTH},
+    sim::{
+        load_level, Archetypes, Command, Level, LevelLoadError, Replay, SaveGame, Simulation,
+        Waves, MAX_SPEED,
+    },
     tower::BuildPoint,
 };
 
@@ -27,22 +35,117 @@ impl Default for GameState {
     }
 }
 
-#[derive(Default)]
 pub struct TowerDefState {
     map: PathBuf,
+    // the parsed map and the level it describes
+    tiled_map: tiled::Map,
+    level: Level,
+    waves: Waves,
+    archetypes: Archetypes,
+    // picked at random when the level starts if not given
+    seed: Option<u64>,
+    // the replay being played back, if we aren't playing
+    replay: Option<Replay>,
+    // the game we are continuing, if any
+    save: Option<SaveGame>,
+    // how fast the game went before it was paused
+    unpaused_speed: u32,
+    // the screen shown once the game is won or lost, and its quit button
+    end: Option<(Entity, Entity)>,
 }
 
 impl TowerDefState {
-    pub fn new(map: PathBuf) -> Self {
-        Self { map }
+    /// Loads the level at `map`, so a broken level is found before we leave
+    /// the menu.
+    pub fn new(map: PathBuf, seed: Option<u64>) -> Result<Self, LevelLoadError> {
+        let (tiled_map, level) = load_level(&map)?;
+        let waves = Waves::for_level(&map, &level)?;
+        let archetypes = Archetypes::for_level(&map)?;
+        Ok(Self {
+            map,
+            tiled_map,
+            level,
+            waves,
+            archetypes,
+            seed,
+            replay: None,
+            save: None,
+            unpaused_speed: 1,
+            end: None,
+        })
+    }
+
+    /// Continues a saved game.
+    pub fn load(save: SaveGame) -> Result<Self, LevelLoadError> {
+        let mut state = Self::new(save.level().to_path_buf(), None)?;
+        state.save = Some(save);
+        Ok(state)
+    }
+
+    /// Plays back a game instead of letting the player play.
+    pub fn replay(replay: Replay) -> Result<Self, LevelLoadError> {
+        let mut state = Self::new(replay.level().to_path_buf(), Some(replay.seed()))?;
+        state.replay = Some(replay);
+        Ok(state)
+    }
+
+    fn call_next_wave(&self, sim: &mut Simulation) {
+        // nobody gets to play during a replay
+        if self.replay.is_none() {
+            // it may have started already
+            let _ = sim.apply(Command::NextWave);
+        }
     }
 
+    /// Tells the player how the game ended, until they quit.
+    fn show_end(&mut self, world: &mut World, title: &str, score: usize) {
+        let font = world.read_resource::<Loader>().load(
+            "font/square.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let text = world
+            .create_entity()
+            .with(UiTransform::new(
+                "end".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                20.,
+                10.,
+                400.,
+                200.,
+            ))
+            .with(UiImage::SolidColor([0.1, 0.1, 0.1, 0.9]))
+            .with(UiText::new(
+                font,
+                format!("{}\nScore {}", title, score),
+                [1., 0.85, 0.3, 1.],
+                40.,
+                LineMode::Wrap,
+                Anchor::Middle,
+            ))
+            .build();
+        let (_, button) = UiButtonBuilder::<(), u32>::new("Quit")
+            .with_font_size(24.0)
+            .with_position(0.0, -110.0)
+            .with_layer(11.0)
+            .with_size(80.0, 32.0)
+            .with_image(UiImage::SolidColor([0.9, 0.9, 0.9, 1.]))
+            .with_anchor(Anchor::Middle)
+            .build_from_world(&world);
+        self.end = Some((text, button.image_entity));
+    }
+
+    /// Starts with the whole map in view, `CameraSystem` fits it to the
+    /// window afterwards.
     fn initialise_camera(&mut self, world: &mut World, map: &tiled::Map) {
         let mut transform = Transform::default();
         let width = (map.width * map.tile_width) as f32;
         let height = (map.height * map.tile_height) as f32;
 
-        transform.set_translation_xyz(width / 2., height / 2., 1.0);
+        transform.set_translation_xyz(width / 2., height / 2., render::CAMERA);
         world
             .create_entity()
             .with(Camera::standard_2d(width, height))
@@ -51,59 +154,16 @@ impl TowerDefState {
     }
 
     fn load_map(&mut self, world: &mut World) {
-        // parse the map
-        let map_file = std::fs::File::open(&self.map).unwrap();
-        let reader = std::io::BufReader::new(map_file);
-        let map = tiled::parse(reader).unwrap();
-        let tile_set = map.get_tileset_by_gid(1).unwrap();
+        let map = self.tiled_map.clone();
+        let level = self.level.clone();
         self.initialise_camera(world, &map);
 
-        let sprite_sheets = map::create_sprite_sheets(tile_set, world);
-        let sprite_sheet_handle = &sprite_sheets[0];
-
-        // which tile ids are our roads
-        let mut roads = vec![];
-        // which tile ids are our construction points
-        let mut construction_points = vec![];
-        // which tile id is the starting point
-        let mut start_point = None;
-        let mut end_point = None;
-        let mut directions = HashMap::new();
-        for tile in map.tilesets[0].tiles.iter() {
-            if tile.properties.contains_key("road") {
-                roads.push(tile.id);
-                let mut dir: u8 = 0b0000;
-                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("up") {
-                    dir |= 0b0001;
-                }
-                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("right") {
-                    dir |= 0b0010;
-                }
-                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("down") {
-                    dir |= 0b0100;
-                }
-                if let Some(tiled::PropertyValue::BoolValue(true)) = tile.properties.get("left") {
-                    dir |= 0b1000;
-                }
-                directions.insert(tile.id, dir);
-            } else if tile.properties.contains_key("construction-point") {
-                construction_points.push(tile.id);
-            } else if tile.properties.contains_key("start-point") {
-                start_point = Some(tile.id);
-            } else if tile.properties.contains_key("end-point") {
-                end_point = Some(tile.id);
-            }
-        }
-        assert!(start_point.is_some(), "No tile defined as starting point!");
-        assert!(end_point.is_some(), "No tile defined as end point!");
-        let start_point = start_point.unwrap();
-        let end_point = end_point.unwrap();
-        let (tile_width, tile_height) = (tile_set.tile_width, tile_set.tile_height);
-        // each entry represents whether [x][y] can be walked on
-        let mut road_map: Vec<Vec<u8>> = vec![vec![0; map.width as usize]; map.height as usize];
-        let mut start_coord = None;
-        let mut end_coord = None;
-        for layer in map.layers.iter().rev() {
+        let sprite_sheets = map::create_tileset_sprite_sheets(&map, world);
+
+        let (tile_width, tile_height) = (map.tile_width, map.tile_height);
+        for layer in map.layers.iter().filter(|l| l.visible) {
+            // each layer is drawn in its own band, above the ones before it
+            let z = render::map_layer(layer.layer_index);
             for (y, row) in layer.tiles.iter().rev().enumerate().clone() {
                 for (x, &tile) in row.iter().enumerate() {
                     // Do nothing with empty tiles
@@ -111,22 +171,10 @@ impl TowerDefState {
                         continue;
                     }
 
-                    // Tile ids start from 1 but tileset sprites start from 0
-                    let tile_id = tile.gid - 1;
-                    if roads.binary_search(&tile_id).is_ok() {
-                        road_map[x][y] = directions[&tile_id];
-                    } else if tile_id == start_point {
-                        start_coord = Some(Coord::new(x, y));
-                        road_map[x][y] = 0b1111;
-                    } else if tile_id == end_point {
-                        end_coord = Some(Coord::new(x, y));
-                        road_map[x][y] = 0b1111;
-                    }
-
-                    // Sprite for the tile
-                    let tile_sprite = SpriteRender {
-                        sprite_sheet: sprite_sheet_handle.clone(),
-                        sprite_number: tile_id as usize,
+                    // Sprite for the tile, from the tileset it belongs to
+                    let tile_sprite = match map::tile_sprite(&map, &sprite_sheets, tile.gid) {
+                        Some(sprite) => sprite,
+                        None => continue,
                     };
 
                     // Where should we draw the tile?
@@ -141,16 +189,21 @@ impl TowerDefState {
                     tile_transform.set_translation_xyz(
                         offset_x + x_coord as f32,
                         offset_y + y_coord as f32,
-                        -1.0,
+                        z,
                     );
 
                     // Create the tile entity
-                    let entity = world
+                    let mut entity = world
                         .create_entity()
                         .with(tile_transform)
                         .with(tile_sprite.clone());
+                    // tiles can be animated in their tileset
+                    if let Some(animation) = map::tile_animation(&map, tile.gid) {
+                        entity = entity.with(animation);
+                    }
+                    entity = with_opacity(entity, layer.opacity);
                     // if it is a build point, make sure to add that component as well
-                    if construction_points.binary_search(&tile_id).is_ok() {
+                    if level.build_points().contains(&Coord::new(x, y)) {
                         entity.with(BuildPoint::new(Coord::new(x, y)))
                     } else {
                         entity
@@ -159,66 +212,173 @@ impl TowerDefState {
                 }
             }
         }
-        let paths = gather_paths(vec![start_coord.unwrap()], end_coord.unwrap(), &road_map);
-        // create the text which lets you know how many resources you have left
+        // tile objects are decorations, drawn in the band of their layer
+        let map_height = (map.height * map.tile_height) as f32;
+        for group in map.object_groups.iter().filter(|g| g.visible) {
+            let z = group.layer_index.map_or(render::MAP, render::map_layer);
+            for object in group.objects.iter().filter(|o| o.visible && o.gid != 0) {
+                let sprite = match map::tile_sprite(&map, &sprite_sheets, object.gid) {
+                    Some(sprite) => sprite,
+                    None => continue,
+                };
+                // the position of tile objects is their bottom left corner
+                let mut transform = Transform::default();
+                transform.set_translation_xyz(
+                    object.x + object.width / 2.0,
+                    map_height - object.y + object.height / 2.0,
+                    z,
+                );
+                let mut entity = world.create_entity().with(transform).with(sprite);
+                if let Some(animation) = map::tile_animation(&map, object.gid) {
+                    entity = entity.with(animation);
+                }
+                with_opacity(entity, group.opacity).build();
+            }
+        }
+        // the bar at the top comes from a prefab, the rest is laid out here
+        world.exec(|mut creator: UiCreator<'_>| creator.create(hud::PREFAB, ()));
         let font = world.read_resource::<Loader>().load(
             "font/square.ttf",
             TtfFormat,
             (),
             &world.read_resource(),
         );
-        let gold_text = world
+        let tooltip = world
             .create_entity()
             .with(UiTransform::new(
-                "gold-text".to_string(),
+                "tooltip".to_string(),
                 Anchor::TopLeft,
                 Anchor::TopLeft,
-                50.,
-                -50.,
-                0.0,
-                200.,
-                50.,
+                0.,
+                0.,
+                3.0,
+                300.,
+                20.,
             ))
             .with(UiText::new(
                 font.clone(),
-                "100 gold".to_string(),
-                [0., 1., 1., 1.],
-                50.,
+                String::new(),
+                [1., 1., 1., 1.],
+                16.,
                 LineMode::Single,
+                Anchor::MiddleLeft,
+            ))
+            .with(UiImage::SolidColor([0.1, 0.1, 0.1, 0.8]))
+            .with(Hidden)
+            .build();
+        // shown while a tower is selected
+        let tower_info = world
+            .create_entity()
+            .with(UiTransform::new(
+                "tower-info".to_string(),
+                Anchor::BottomRight,
+                Anchor::BottomRight,
+                -20.,
+                20.,
+                3.0,
+                320.,
+                200.,
+            ))
+            .with(UiText::new(
+                font.clone(),
+                String::new(),
+                [1., 1., 1., 1.],
+                18.,
+                LineMode::Wrap,
                 Anchor::TopLeft,
             ))
+            .with(UiImage::SolidColor([0.1, 0.1, 0.1, 0.8]))
+            .with(Hidden)
             .build();
-        let error_text = world
+        // the boss health bar stays hidden until a boss shows up
+        let boss_bar_background = world
             .create_entity()
             .with(UiTransform::new(
-                "error-text".to_string(),
+                "boss-bar".to_string(),
                 Anchor::TopMiddle,
-                Anchor::TopMiddle,
-                50.,
-                -50.,
+                Anchor::Middle,
+                0.,
+                -60.,
                 0.0,
-                550.,
-                50.,
+                BOSS_BAR_WIDTH,
+                16.,
+            ))
+            .with(UiImage::SolidColor([0.2, 0.2, 0.2, 1.]))
+            .with(Hidden)
+            .build();
+        let boss_bar_fill = world
+            .create_entity()
+            .with(UiTransform::new(
+                "boss-bar-fill".to_string(),
+                Anchor::TopMiddle,
+                Anchor::MiddleLeft,
+                -BOSS_BAR_WIDTH / 2.,
+                -60.,
+                0.1,
+                BOSS_BAR_WIDTH,
+                16.,
+            ))
+            .with(UiImage::SolidColor([0.8, 0.1, 0.1, 1.]))
+            .with(Hidden)
+            .build();
+        let boss_name = world
+            .create_entity()
+            .with(UiTransform::new(
+                "boss-name".to_string(),
+                Anchor::TopMiddle,
+                Anchor::Middle,
+                0.,
+                -60.,
+                0.2,
+                BOSS_BAR_WIDTH,
+                16.,
             ))
             .with(UiText::new(
                 font.clone(),
-                "No errors.".to_string(),
-                [1., 0., 0., 0.],
-                50.,
+                String::new(),
+                [1., 1., 1., 1.],
+                14.,
                 LineMode::Single,
-                Anchor::TopLeft,
+                Anchor::Middle,
             ))
+            .with(Hidden)
             .build();
         world
             .create_entity()
             .with(Map::new(
                 map,
-                paths,
-                sprite_sheet_handle.clone(),
-                gold_text,
-                error_text,
+                sprite_sheets,
+                tooltip,
+                tower_info,
+                BossBar::new(boss_bar_background, boss_bar_fill, boss_name),
             ))
             .build();
+        let mut sim = match self.save.take() {
+            Some(save) => save.into_simulation(level),
+            None => {
+                let seed = self.seed.unwrap_or_else(rand::random);
+                Simulation::new(level, self.waves.clone(), seed)
+                    .with_archetypes(self.archetypes.clone())
+            }
+        };
+        // with the seed, the same game can be played again with `--seed`
+        log::info!("Playing {} with seed {}", self.map.display(), sim.seed());
+        if let Some(replay) = &self.replay {
+            sim.play(replay.commands().clone());
+            world.insert(replay.clone());
+        }
+        world.insert(sim);
+    }
+}
+
+/// Makes what `entity` draws see-through, for layers Tiled gave an opacity.
+fn with_opacity(entity: EntityBuilder<'_>, opacity: f32) -> EntityBuilder<'_> {
+    if opacity < 1.0 {
+        entity
+            .with(Tint(Srgba::new(1.0, 1.0, 1.0, opacity)))
+            .with(Transparent)
+    } else {
+        entity
     }
 }
 
@@ -228,62 +388,202 @@ impl SimpleState for TowerDefState {
         world.insert(GameState::Game);
         self.load_map(world);
     }
-}
 
-/// Gets all valid paths that a runner can take.
-fn gather_paths(path: Vec<Coord>, dest: Coord, map: &Vec<Vec<u8>>) -> Vec<Vec<Coord>> {
-    static DIRECTIONS: [(isize, isize, u8); 4] = [
-        (0, 1, 0b0001),
-        (0, -1, 0b0100),
-        (1, 0, 0b0010),
-        (-1, 0, 0b1000),
-    ];
-    // where we are coming from
-    let origin = *path.iter().last().unwrap();
-    // did we just finish a path? if so return it
-    if origin == dest {
-        return vec![path];
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if self.replay.is_some() {
+            return;
+        }
+        let sim = data.world.read_resource::<Simulation>();
+        let level = self.map.file_stem().unwrap().to_string_lossy();
+        // the game can be continued from the main menu, unless it is over
+        let path = PathBuf::from("saves").join(format!("{}.ron", level));
+        if sim.is_won() || sim.is_lost() {
+            match std::fs::remove_file(&path) {
+                Ok(()) => log::info!("Removed the save of the finished game {}", path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("Couldn't remove {}: {}", path.display(), e),
+            }
+        } else {
+            let save = SaveGame::new(self.map.clone(), &sim);
+            match std::fs::create_dir_all("saves").and_then(|_| save.save(&path)) {
+                Ok(()) => log::info!("Saved game to {}", path.display()),
+                Err(e) => log::warn!("Couldn't save game to {}: {}", path.display(), e),
+            }
+        }
+        // keep a replay of every game, e.g. `replays/level-42.ron`
+        let replay = Replay::record(self.map.clone(), &sim);
+        let path = PathBuf::from("replays").join(format!("{}-{}.ron", level, replay.seed()));
+        match std::fs::create_dir_all("replays").and_then(|_| replay.save(&path)) {
+            Ok(()) => log::info!("Saved replay to {}", path.display()),
+            Err(e) => log::warn!("Couldn't save replay to {}: {}", path.display(), e),
+        }
     }
-    let mut neighbours = Vec::with_capacity(4);
-    // up, down, right, left
-    for offset in &DIRECTIONS {
-        let new_c = (origin.x as isize + offset.0, origin.y as isize + offset.1);
-        // if we can go in that direction
-        if new_c.0 >= 0
-            && new_c.1 >= 0
-            && (new_c.0 as usize) < map.len()
-            && (new_c.1 as usize) < map[0].len()
-            // if we can actually move in that direction!
-            && (map[origin.x][origin.y] & offset.2) > 0
-        {
-            let new_coord = Coord::new(new_c.0 as usize, new_c.1 as usize);
-            // make sure we don't create a cycle!
-            if !path.contains(&new_coord) {
-                neighbours.push(Coord::new(new_c.0 as usize, new_c.1 as usize));
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if self.end.is_some() {
+            return Trans::None;
+        }
+        // `SimulationSystem` stops stepping finished games
+        let end = {
+            let sim = data.world.read_resource::<Simulation>();
+            if sim.is_won() {
+                Some(("Victory!", sim.score()))
+            } else if sim.is_lost() {
+                Some(("Game over", sim.score()))
+            } else {
+                None
             }
+        };
+        if let Some((title, score)) = end {
+            self.show_end(data.world, title, score);
         }
+        Trans::None
     }
-    let mut final_paths = vec![];
-    for n in neighbours {
-        // a new path from what we have so far + a neighbour that we can visit
-        let new_path = path.iter().cloned().chain(std::iter::once(n)).collect();
-        let paths = gather_paths(new_path, dest.clone(), map);
-        if path.len() > 0 {
-            final_paths.extend(paths);
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        use amethyst::input::{is_close_requested, is_key_down, InputEvent};
+        use amethyst::winit::VirtualKeyCode;
+
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+                return Trans::Quit;
+            }
+        }
+        // a finished game can only be quit
+        if let Some((_, quit)) = self.end {
+            return match &event {
+                StateEvent::Ui(UiEvent {
+                    event_type: UiEventType::Click,
+                    target,
+                }) if *target == quit => Trans::Quit,
+                _ => Trans::None,
+            };
+        }
+        if let StateEvent::Input(InputEvent::ActionPressed(action)) = &event {
+            let mut sim = data.world.write_resource::<Simulation>();
+            let speed = sim.speed();
+            match action.as_str() {
+                "pause" if speed == 0 => {
+                    sim.apply(Command::SetSpeed {
+                        speed: self.unpaused_speed,
+                    })
+                    .unwrap();
+                }
+                "pause" => {
+                    self.unpaused_speed = speed;
+                    sim.apply(Command::SetSpeed { speed: 0 }).unwrap();
+                }
+                // a paused game starts again at 1x
+                "speed-up" => {
+                    let speed = speed % MAX_SPEED + 1;
+                    sim.apply(Command::SetSpeed { speed }).unwrap();
+                }
+                "next-wave" => self.call_next_wave(&mut sim),
+                _ => {}
+            }
+        }
+        if let StateEvent::Ui(UiEvent {
+            event_type: UiEventType::Click,
+            target,
+        }) = &event
+        {
+            let button = data
+                .world
+                .exec(|finder: UiFinder<'_>| finder.find(hud::CALL_EARLY));
+            if button == Some(*target) {
+                self.call_next_wave(&mut data.world.write_resource::<Simulation>());
+            }
         }
+        Trans::None
     }
-    final_paths
 }
 
 #[derive(Default)]
 pub struct MainMenuState {
     levels: HashMap<Entity, std::path::PathBuf>,
+    saves: HashMap<Entity, std::path::PathBuf>,
+    // the seed levels are played with
+    seed: Option<u64>,
+    // the dialog telling why a level couldn't be loaded, and its button
+    dialog: Option<(Entity, Entity)>,
+    // opens the screen which rebinds the controls
+    controls: Option<Entity>,
 }
 
 impl MainMenuState {
-    pub fn new() -> Self {
+    pub fn new(seed: Option<u64>) -> Self {
         Self {
             levels: Default::default(),
+            saves: Default::default(),
+            seed,
+            dialog: None,
+            controls: None,
+        }
+    }
+
+    /// Tells the player that something went wrong, until they click "OK".
+    fn show_dialog(&mut self, world: &mut World, message: String) {
+        self.close_dialog(world);
+        let font = world.read_resource::<Loader>().load(
+            "font/square.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let text = world
+            .create_entity()
+            .with(UiTransform::new(
+                "dialog".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                20.,
+                1.0,
+                600.,
+                300.,
+            ))
+            .with(UiImage::SolidColor([0.1, 0.1, 0.1, 0.9]))
+            .with(UiText::new(
+                font,
+                message,
+                [1., 0.4, 0.4, 1.],
+                20.,
+                LineMode::Wrap,
+                Anchor::Middle,
+            ))
+            .build();
+        let (_, button) = UiButtonBuilder::<(), u32>::new("OK")
+            .with_font_size(24.0)
+            .with_position(0.0, -160.0)
+            .with_layer(2.0)
+            .with_size(80.0, 32.0)
+            .with_image(UiImage::SolidColor([0.9, 0.9, 0.9, 1.]))
+            .with_anchor(Anchor::Middle)
+            .build_from_world(&world);
+        self.dialog = Some((text, button.image_entity));
+    }
+
+    fn close_dialog(&mut self, world: &mut World) {
+        if let Some((text, button)) = self.dialog.take() {
+            world.delete_entity(text).unwrap();
+            world.delete_entity(button).unwrap();
+        }
+    }
+
+    /// Deletes the buttons of the menu, before going somewhere else.
+    fn clear(&mut self, world: &mut World) {
+        // XXX: is there a better way of doing this? Like a hide method?
+        let buttons = self
+            .levels
+            .drain()
+            .chain(self.saves.drain())
+            .map(|(e, _)| e);
+        for e in buttons.chain(self.controls.take()) {
+            world.delete_entity(e).unwrap();
         }
     }
 }
@@ -293,7 +593,20 @@ impl SimpleState for MainMenuState {
         use std::fs;
 
         let world = data.world;
-        let paths = fs::read_dir("assets/").unwrap();
+        let (_, controls) = UiButtonBuilder::<(), u32>::new("Controls")
+            .with_font_size(24.0)
+            .with_position(-32.0, -32.0)
+            .with_size(150.0, 32.0)
+            .with_image(UiImage::SolidColor([0.9, 0.9, 0.9, 1.]))
+            .with_anchor(Anchor::TopRight)
+            .build_from_world(&world);
+        self.controls = Some(controls.image_entity);
+        let paths = match fs::read_dir("assets/") {
+            Ok(paths) => paths,
+            Err(e) => {
+                return self.show_dialog(world, format!("Couldn't look for levels: {}", e));
+            }
+        };
         let tmxs: Vec<std::path::PathBuf> = paths
             .filter_map(|p| {
                 if p.is_err() {
@@ -324,6 +637,21 @@ impl SimpleState for MainMenuState {
                 .build_from_world(&world);
             self.levels.insert(button.image_entity.clone(), path);
         }
+        // and a button for each game which can be continued
+        let saves: Vec<std::path::PathBuf> = fs::read_dir("saves/")
+            .map(|paths| paths.filter_map(|p| p.ok()).map(|p| p.path()).collect())
+            .unwrap_or_else(|_| vec![]);
+        for (i, path) in saves.into_iter().enumerate() {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let (_, button) = UiButtonBuilder::<(), u32>::new(format!("Continue {}", name))
+                .with_font_size(24.0)
+                .with_position(32.0, -80.0 - i as f32 * 40.0)
+                .with_size(300.0, 32.0)
+                .with_image(UiImage::SolidColor([0.9, 0.9, 0.9, 1.]))
+                .with_anchor(Anchor::TopLeft)
+                .build_from_world(&world);
+            self.saves.insert(button.image_entity.clone(), path);
+        }
     }
 
     fn handle_event(
@@ -349,14 +677,39 @@ impl SimpleState for MainMenuState {
                     target,
                 } = ui_event
                 {
-                    // get the path to the map that we are loading
-                    let path = self.levels[&target].clone();
-                    // hide buttons by deleting them
-                    // XXX: is there a better way of doing this? Like a hide method?
-                    for (e, _) in self.levels.drain() {
-                        data.world.delete_entity(e).unwrap();
+                    if let Some((_, button)) = self.dialog {
+                        if *target == button {
+                            self.close_dialog(data.world);
+                        }
+                        return Trans::None;
+                    }
+                    if Some(*target) == self.controls {
+                        self.clear(data.world);
+                        return Trans::Switch(Box::new(ControlsState::new(self.seed)));
                     }
-                    Trans::Switch(Box::new(TowerDefState::new(path)))
+                    let state = if let Some(path) = self.levels.get(&target) {
+                        // get the path to the map that we are loading
+                        TowerDefState::new(path.clone(), self.seed)
+                            .map_err(|e| (path, e.to_string()))
+                    } else if let Some(path) = self.saves.get(&target) {
+                        SaveGame::load(path)
+                            .map_err(|e| e.to_string())
+                            .and_then(|save| TowerDefState::load(save).map_err(|e| e.to_string()))
+                            .map_err(|e| (path, e))
+                    } else {
+                        return Trans::None;
+                    };
+                    let state = match state {
+                        Ok(state) => state,
+                        // a broken level shouldn't take the game down, so we stay in the menu
+                        Err((path, e)) => {
+                            let message = format!("Couldn't load {}:\n{}", path.display(), e);
+                            self.show_dialog(data.world, message);
+                            return Trans::None;
+                        }
+                    };
+                    self.clear(data.world);
+                    Trans::Switch(Box::new(state))
                 } else {
                     Trans::None
                 }
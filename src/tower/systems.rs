@@ -1,388 +1,653 @@
-use amethyst::assets::Handle;
-use amethyst::core::timing::Time;
-use amethyst::core::Transform;
+use amethyst::assets::{AssetStorage, Handle, Loader};
+use amethyst::core::{Hidden, Transform};
 use amethyst::derive::SystemDesc;
 use amethyst::ecs::{
-    Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, WriteStorage,
+    Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, Write, WriteStorage,
 };
-use amethyst::input::{InputHandler, StringBindings};
-use amethyst::renderer::{Camera, SpriteRender};
-use amethyst::ui::UiText;
-use amethyst::window::ScreenDimensions;
+use amethyst::input::{InputEvent, InputHandler, StringBindings};
+use amethyst::renderer::{
+    palette::Srgba, resources::Tint, ImageFormat, Sprite, SpriteRender, SpriteSheet, Texture,
+    Transparent,
+};
+use amethyst::shrev::{EventChannel, ReaderId};
+use amethyst::ui::{UiText, UiTransform};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::tower::{utils, BuildPoint, Missle, Tower, TowerKind, MISSLE_SPEED};
-use crate::{map::Map, runner::Runner, GameState};
+use crate::tower::{utils, BuildPoint};
+use crate::{
+    animation::Animation,
+    clicks::ClickEvent,
+    controls::BUILD_ACTIONS,
+    hud::HudEvent,
+    map::{self, Map},
+    render,
+    sim::{Command, CommandError, Id, Replay, Simulation, TowerKind},
+    Coord, GameState,
+};
 
+/// Keeps an entity around for each tower of the simulation, plays its attack
+/// animation when it fires, and hides the build points towers were built on.
 #[derive(SystemDesc)]
-pub struct TowerSystem;
+pub struct TowerSystem {
+    // the entity and last known cooldown of each tower
+    towers: HashMap<Id, (Entity, f32)>,
+}
+
+impl TowerSystem {
+    pub fn new() -> Self {
+        Self {
+            towers: Default::default(),
+        }
+    }
+}
 
 impl<'s> System<'s> for TowerSystem {
     type SystemData = (
-        WriteStorage<'s, Transform>,
-        ReadStorage<'s, Runner>,
-        WriteStorage<'s, Tower>,
+        Option<Read<'s, Simulation>>,
         ReadStorage<'s, Map>,
-        WriteStorage<'s, Missle>,
+        ReadStorage<'s, BuildPoint>,
+        WriteStorage<'s, Transform>,
         WriteStorage<'s, SpriteRender>,
-        Read<'s, Time>,
+        WriteStorage<'s, Animation>,
+        WriteStorage<'s, Hidden>,
         Read<'s, GameState>,
         Entities<'s>,
     );
 
     fn run(
         &mut self,
-        (mut transforms, runners, mut towers, map, mut missles, mut sprites, time, state, entities): Self::SystemData,
+        (
+            sim,
+            map,
+            points,
+            mut transforms,
+            mut sprites,
+            mut animations,
+            mut hidden,
+            state,
+            entities,
+        ): Self::SystemData,
     ) {
         if *state != GameState::Game {
             return;
         }
+        let sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
         let map = (&map).join().next().unwrap();
-        let mut missle_comps = vec![];
-        let time = time.delta_seconds();
-        for (tower, t_trans) in (&mut towers, &transforms).join() {
-            tower.tick(time);
-            for (_, r_trans, ent) in (&runners, &transforms, &entities).join() {
-                if utils::in_range(&t_trans, tower.radius(), &r_trans) && tower.cd() <= 0. {
-                    let debuff = tower.debuff();
-                    missle_comps.push((
-                        Missle::new(ent.id(), tower.damage(), debuff),
-                        t_trans.clone(),
-                    ));
-                    tower.reset_cd();
+        // upgraded towers get a new id, so the old entity goes away
+        let built: HashSet<Id> = sim.towers().iter().map(|t| t.id()).collect();
+        self.towers.retain(|id, (ent, _)| {
+            if !built.contains(id) {
+                entities.delete(*ent).unwrap();
+            }
+            built.contains(id)
+        });
+        for tower in sim.towers() {
+            let sprite = tower.sprite_number();
+            let (ent, cd) = self.towers.entry(tower.id()).or_insert_with(|| {
+                let sprite_render = SpriteRender {
+                    sprite_sheet: map.sprite_sheet_handle(),
+                    sprite_number: sprite,
+                };
+                let mut trans = tower
+                    .pos()
+                    .to_trans(map.tile_width() as usize, map.tile_height() as usize);
+                trans.translation_mut().z = render::TOWERS;
+                let ent = entities
+                    .build_entity()
+                    .with(trans, &mut transforms)
+                    .with(sprite_render, &mut sprites)
+                    .build();
+                (ent, tower.cd())
+            });
+            // the cooldown goes back up when the tower fires
+            if tower.cd() > *cd {
+                if let Some(attack) = map.attack_animation(sprite) {
+                    animations.insert(*ent, attack.then_show(sprite)).unwrap();
+                }
+            }
+            *cd = tower.cd();
+            // towers idle once they're done attacking
+            if !animations.contains(*ent) {
+                if let Some(idle) = map.animation(sprite) {
+                    animations.insert(*ent, idle).unwrap();
                 }
             }
         }
-        for (missle, mut trans) in missle_comps {
-            let sprite = SpriteRender {
-                sprite_sheet: map.sprite_sheet_handle(),
-                sprite_number: 143,
-            };
-            trans.translation_mut().z = 0.5;
-            entities
-                .build_entity()
-                .with(missle, &mut missles)
-                .with(trans, &mut transforms)
-                .with(sprite, &mut sprites)
-                .build();
+        for (ent, point) in (&entities, &points).join() {
+            if sim.tower_at(point.pos()).is_some() {
+                hidden.insert(ent, Hidden).unwrap();
+            } else {
+                hidden.remove(ent);
+            }
         }
     }
 }
 
+/// Colour of the borders around the buttons of the build menu.
+const BORDER_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 1.0];
+/// Colour of the border around the button under the mouse.
+const HOVER_COLOR: [f32; 4] = [0.9, 0.8, 0.2, 1.0];
+/// How much darker towers the player can't afford are drawn.
+const DISABLED_SHADE: f32 = 0.35;
+
+/// Colour of the circle showing the range of a tower.
+const RANGE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.25];
+/// The image of the range circle, and its size in pixels.
+const RANGE_IMAGE: &str = "range.png";
+const RANGE_IMAGE_SIZE: u32 = 128;
+
+/// A tower offered by the build menu, drawn over a bordered background.
+#[derive(Clone, Copy)]
+struct MenuButton {
+    kind: TowerKind,
+    border: Entity,
+    icon: Entity,
+}
+
+/// Shows the towers which can be built on (or upgraded to from) the build
+/// point the player clicked, and builds the one they pick.
 #[derive(SystemDesc)]
+#[system_desc(name(BuildPointSystemDesc))]
 pub struct BuildPointSystem {
-    // currently selected build point
-    selected: Option<Entity>,
-    selector: Vec<(Entity, TowerKind)>,
-    menus: HashMap<TowerKind, Vec<(Entity, TowerKind)>>,
-    shown: Vec<(Entity, TowerKind)>,
+    #[system_desc(event_channel_reader)]
+    reader: ReaderId<ClickEvent>,
+    // the keyboard commands
+    #[system_desc(event_channel_reader)]
+    input_reader: ReaderId<InputEvent<StringBindings>>,
+    // currently selected build point or tower
+    #[system_desc(skip)]
+    selected: Option<Coord>,
+    // where the mouse is, in world coordinates
+    #[system_desc(skip)]
+    hover: Option<Transform>,
+    // the buttons offered on empty build points (`None`) and on each tower
+    #[system_desc(skip)]
+    menus: HashMap<Option<TowerKind>, Vec<MenuButton>>,
+    #[system_desc(skip)]
+    shown: Vec<MenuButton>,
+    // sprite sheets of the borders and of the hovered border
+    #[system_desc(skip)]
+    sheets: Option<(Handle<SpriteSheet>, Handle<SpriteSheet>)>,
+    // the sprite sheet of the range circle
+    #[system_desc(skip)]
+    range_sheet: Option<Handle<SpriteSheet>>,
+    // draws the range of the tower the player is looking at
+    #[system_desc(skip)]
+    range_circle: Option<Entity>,
 }
 
 impl BuildPointSystem {
-    pub fn new() -> Self {
+    pub fn new(
+        reader: ReaderId<ClickEvent>,
+        input_reader: ReaderId<InputEvent<StringBindings>>,
+    ) -> Self {
         Self {
+            reader,
+            input_reader,
             selected: None,
-            selector: Vec::with_capacity(2),
+            hover: None,
             menus: Default::default(),
             shown: vec![],
+            sheets: None,
+            range_sheet: None,
+            range_circle: None,
         }
     }
 
-    fn maybe_init<'s>(
+    fn maybe_init(
         &mut self,
-        handle: Handle<amethyst::renderer::SpriteSheet>,
-        sprites: &mut WriteStorage<'s, SpriteRender>,
-        transforms: &mut WriteStorage<'s, Transform>,
+        loader: &Loader,
+        texture_storage: &AssetStorage<Texture>,
+        sprite_sheet_storage: &AssetStorage<SpriteSheet>,
+    ) {
+        if self.sheets.is_none() {
+            self.sheets = Some((
+                map::create_color_sprite_sheet(
+                    BORDER_COLOR,
+                    loader,
+                    texture_storage,
+                    sprite_sheet_storage,
+                ),
+                map::create_color_sprite_sheet(
+                    HOVER_COLOR,
+                    loader,
+                    texture_storage,
+                    sprite_sheet_storage,
+                ),
+            ));
+        }
+        if self.range_sheet.is_none() {
+            let texture = loader.load(RANGE_IMAGE, ImageFormat::default(), (), texture_storage);
+            let size = RANGE_IMAGE_SIZE;
+            let sprite_sheet = SpriteSheet {
+                texture,
+                sprites: vec![Sprite::from_pixel_values(
+                    size, size, size, size, 0, 0, [0.0; 2], false, false,
+                )],
+            };
+            self.range_sheet = Some(loader.load_from_data(sprite_sheet, (), sprite_sheet_storage));
+        }
+    }
+
+    /// Shows the menu of the build point at `trans`, right above it.
+    fn show_menu<'s>(
+        &mut self,
+        tower: Option<TowerKind>,
+        trans: &Transform,
+        map: &Map,
         entities: &Entities<'s>,
+        (sprites, transforms, hidden): MenuStorages<'_, 's>,
     ) {
-        if self.selector.len() == 0 {
-            let mut tr = Transform::default();
-            // out of sight
-            tr.translation_mut().z = 2.0;
-            for tk in [TowerKind::Simple, TowerKind::Frost].iter() {
-                self.selector.push((
-                    entities
+        self.hide_all(hidden);
+        let (border_sheet, _) = self.sheets.clone().unwrap();
+        let kinds = match tower {
+            Some(kind) => kind.upgrades(),
+            None => TowerKind::buildable().to_vec(),
+        };
+        let buttons = self.menus.entry(tower).or_insert_with(|| {
+            kinds
+                .into_iter()
+                .map(|kind| MenuButton {
+                    kind,
+                    border: entities
                         .build_entity()
                         .with(
                             SpriteRender {
-                                sprite_sheet: handle.clone(),
-                                sprite_number: tk.sprite_number(),
+                                sprite_sheet: border_sheet.clone(),
+                                sprite_number: 0,
                             },
                             sprites,
                         )
-                        .with(tr.clone(), transforms)
+                        .with(Transform::default(), transforms)
                         .build(),
-                    *tk,
-                ));
-            }
-        }
-    }
-
-    fn show_selector<'s>(
-        &mut self,
-        mut trans: Transform,
-        transforms: &mut WriteStorage<'s, Transform>,
-    ) {
-        self.hide_all(transforms);
-        trans.translation_mut().z = 0.5;
-        trans.translation_mut().y += 16.0;
-        trans.translation_mut().x -= 24.0;
-        for (button, tk) in &self.selector {
-            trans.translation_mut().x += 16.0;
-            *transforms.get_mut(button.clone()).unwrap() = trans.clone();
-            self.shown.push((button.clone(), *tk));
+                    icon: entities
+                        .build_entity()
+                        .with(
+                            SpriteRender {
+                                sprite_sheet: map.sprite_sheet_handle(),
+                                sprite_number: kind.sprite_number(),
+                            },
+                            sprites,
+                        )
+                        .with(Transform::default(), transforms)
+                        .build(),
+                })
+                .collect()
+        });
+        // the buttons are centered above the build point, a bit apart
+        let (tile_w, tile_h) = (map.tile_width() as f32, map.tile_height() as f32);
+        let step = tile_w + 4.0;
+        let mut x = trans.translation().x - step * (buttons.len() as f32 - 1.0) / 2.0;
+        let y = trans.translation().y + tile_h + 2.0;
+        for button in buttons.iter() {
+            let border = transforms.get_mut(button.border).unwrap();
+            border.set_translation_xyz(x, y, render::OVERLAYS);
+            border.set_scale([tile_w + 2.0, tile_h + 2.0, 1.0].into());
+            transforms
+                .get_mut(button.icon)
+                .unwrap()
+                .set_translation_xyz(x, y, render::OVERLAYS + 0.01);
+            hidden.remove(button.border);
+            hidden.remove(button.icon);
+            x += step;
         }
+        self.shown = buttons.clone();
     }
 
-    fn maybe_init_menu<'s>(
-        &mut self,
-        handle: Handle<amethyst::renderer::SpriteSheet>,
-        sprites: &mut WriteStorage<'s, SpriteRender>,
-        transforms: &mut WriteStorage<'s, Transform>,
-        entities: &Entities<'s>,
-    ) {
-        for tk in [TowerKind::Simple, TowerKind::Frost, TowerKind::Turret].iter() {
-            if !self.menus.contains_key(tk) {
-                let mut tr = Transform::default();
-                // out of sight
-                tr.translation_mut().z = 2.0;
-                self.menus.insert(
-                    *tk,
-                    tk.upgrades()
-                        .into_iter()
-                        .map(|tk| {
-                            (
-                                entities
-                                    .build_entity()
-                                    .with(
-                                        SpriteRender {
-                                            sprite_sheet: handle.clone(),
-                                            sprite_number: tk.sprite_number(),
-                                        },
-                                        sprites,
-                                    )
-                                    .with(tr.clone(), transforms)
-                                    .build(),
-                                tk,
-                            )
-                        })
-                        .collect(),
-                );
-            }
+    fn hide_all<'s>(&mut self, hidden: &mut WriteStorage<'s, Hidden>) {
+        for button in self.shown.drain(..) {
+            hidden.insert(button.border, Hidden).unwrap();
+            hidden.insert(button.icon, Hidden).unwrap();
         }
     }
-
-    fn show_menu<'s>(
-        &mut self,
-        tk: TowerKind,
-        mut trans: Transform,
-        transforms: &mut WriteStorage<'s, Transform>,
-    ) {
-        self.hide_all(transforms);
-        trans.translation_mut().z = 0.5;
-        trans.translation_mut().y += 16.0;
-        trans.translation_mut().x -= 24.0;
-        for (button, tk) in &self.menus[&tk] {
-            trans.translation_mut().x += 16.0;
-            *transforms.get_mut(button.clone()).unwrap() = trans.clone();
-            self.shown.push((button.clone(), *tk));
-        }
-    }
-
-    fn hide_all<'s>(&mut self, transforms: &mut WriteStorage<'s, Transform>) {
-        // hide the menu
-        let _ = self
-            .shown
-            .drain(..)
-            .map(|(e, _)| transforms.get_mut(e).unwrap().translation_mut().z = 2.0)
-            .collect::<Vec<()>>();
-    }
 }
 
+/// The storages the build menu is drawn with.
+type MenuStorages<'a, 's> = (
+    &'a mut WriteStorage<'s, SpriteRender>,
+    &'a mut WriteStorage<'s, Transform>,
+    &'a mut WriteStorage<'s, Hidden>,
+);
+
 impl<'s> System<'s> for BuildPointSystem {
     type SystemData = (
+        Option<Write<'s, Simulation>>,
+        Option<Read<'s, Replay>>,
         WriteStorage<'s, Transform>,
         ReadStorage<'s, BuildPoint>,
         Read<'s, InputHandler<StringBindings>>,
-        ReadExpect<'s, ScreenDimensions>,
-        ReadStorage<'s, Camera>,
+        Read<'s, EventChannel<ClickEvent>>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Write<'s, EventChannel<HudEvent>>,
         Entities<'s>,
-        WriteStorage<'s, Tower>,
         WriteStorage<'s, SpriteRender>,
-        WriteStorage<'s, Map>,
+        ReadStorage<'s, Map>,
         WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, Hidden>,
+        WriteStorage<'s, Tint>,
+        WriteStorage<'s, Transparent>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, AssetStorage<SpriteSheet>>,
         Read<'s, GameState>,
     );
 
     fn run(
         &mut self,
         (
+            sim,
+            replay,
             mut transforms,
             points,
             input,
-            dim,
-            camera,
+            click_events,
+            input_events,
+            mut hud,
             entities,
-            mut towers,
             mut sprites,
-            mut map,
+            map,
             mut texts,
+            mut ui_transforms,
+            mut hidden,
+            mut tints,
+            mut transparent,
+            loader,
+            texture_storage,
+            sprite_sheet_storage,
             state,
         ): Self::SystemData,
     ) {
-        if *state != GameState::Game {
+        // events are read every frame, so none are left over when a game starts
+        let clicks: Vec<ClickEvent> = click_events.read(&mut self.reader).cloned().collect();
+        let pressed: Vec<String> = input_events
+            .read(&mut self.input_reader)
+            .filter_map(|event| match event {
+                InputEvent::ActionPressed(action) => Some(action.clone()),
+                _ => None,
+            })
+            .collect();
+        // nobody gets to play during a replay
+        if *state != GameState::Game || replay.is_some() {
             return;
         }
-        let map = (&mut map).join().next().unwrap();
-        let handle = map.sprite_sheet_handle();
-        // initialise our hidden buttons (if they're not already)
-        self.maybe_init(handle.clone(), &mut sprites, &mut transforms, &entities);
-        self.maybe_init_menu(handle.clone(), &mut sprites, &mut transforms, &entities);
+        let mut sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
+        let map = (&map).join().next().unwrap();
+        self.maybe_init(&loader, &texture_storage, &sprite_sheet_storage);
+        let (border_sheet, hover_sheet) = self.sheets.clone().unwrap();
 
-        let (camera, camera_trans) = (&camera, &transforms).join().next().unwrap();
-        if let Some(mouse_trans) = utils::mouse_position(&input, &dim, camera, camera_trans) {
-            // check if we clicked on any build points
-            for (ent, _, trans) in (&entities, &points, &transforms).join() {
-                if utils::in_range(trans, (map.tile_width() / 2) as f32, &mouse_trans) {
-                    // mark the build point as the currently selected one
-                    self.selected = Some(ent);
-                    // show the selector
-                    self.show_selector(trans.clone(), &mut transforms);
-                    return;
+        // the keyboard works on the selected build point or tower
+        let just_pressed = |action: &str| pressed.iter().any(|a| a == action);
+        let sell = just_pressed("sell");
+        let retarget = just_pressed("retarget");
+        let upgrade = just_pressed("upgrade");
+        let build: Vec<bool> = BUILD_ACTIONS.iter().map(|&a| just_pressed(a)).collect();
+        let command = match self.selected.map(|pos| (pos, sim.tower_at(pos))) {
+            Some((pos, Some(_))) if sell => Some(Command::Sell { pos }),
+            Some((pos, Some(tower))) if retarget => {
+                let targeting = tower.targeting().next();
+                let message = format!("Targeting the {} runner", targeting.name());
+                hud.single_write(HudEvent::Message(message));
+                Some(Command::SetTargeting { pos, targeting })
+            }
+            Some((pos, Some(tower))) if upgrade => tower
+                .upgrades()
+                .first()
+                .map(|&kind| Command::Upgrade { pos, kind }),
+            Some((pos, None)) => TowerKind::buildable()
+                .iter()
+                .zip(build)
+                .find(|(_, pressed)| *pressed)
+                .map(|(&kind, _)| Command::Build { pos, kind }),
+            _ => None,
+        };
+        if let Some(command) = command {
+            let deselect = !matches!(command, Command::SetTargeting { .. });
+            match sim.apply(command) {
+                Ok(()) if deselect => {
+                    self.hide_all(&mut hidden);
+                    self.selected = None;
                 }
+                Ok(()) => {}
+                Err(err) => hud.single_write(HudEvent::Message(err.to_string())),
             }
+        } else if clicks.iter().any(|e| matches!(e, ClickEvent::Cancelled)) {
+            self.hide_all(&mut hidden);
+            self.selected = None;
+        }
 
-            // check if we clicked on any towers
-            for (ent, tower, trans) in (&entities, &towers, &transforms).join() {
-                if utils::in_range(trans, (map.tile_width() / 2) as f32, &mouse_trans) {
-                    // mark the tower as the currently selected one
-                    self.selected = Some(ent);
-                    // show the tower upgrade menu
-                    self.show_menu(tower.kind(), trans.clone(), &mut transforms);
-                    return;
-                }
+        // the selected tower is described, with stats updated as it fights
+        let tower_info = map.tower_info();
+        match self.selected.and_then(|pos| sim.tower_at(pos)) {
+            Some(tower) => {
+                texts.get_mut(tower_info).unwrap().text = utils::describe(tower);
+                hidden.remove(tower_info);
             }
+            None => {
+                hidden.insert(tower_info, Hidden).unwrap();
+            }
+        }
 
-            let mut tower = None;
-            // did we click on a button that is enabled?
-            for (button, tk) in &self.shown {
-                let trans = transforms.get_mut(button.clone()).unwrap();
-                if self.selected.is_some()
-                    && tower.is_none()
-                    && utils::in_range(trans, (map.tile_width() / 2) as f32, &mouse_trans)
-                {
-                    if tk.cost() <= map.gold() {
-                        map.remove_gold(tk.cost());
-                        texts.get_mut(map.gold_text()).unwrap().text =
-                            format!("{} gold", map.gold());
-                        let pos = if let Some(point) = points.get(self.selected.clone().unwrap()) {
-                            point.pos()
-                        } else {
-                            // if it wasn't a build point, we must've upgraded a tower
-                            towers.get(self.selected.clone().unwrap()).unwrap().pos()
-                        };
-                        tower = Some(Tower::new(*tk, pos));
-                        // can't click on two buttons...
-                        break;
-                    } else {
-                        // we don't want to hide the menu selector!
-                        let error_text = texts.get_mut(map.error_text()).unwrap();
-                        error_text.text = format!("Not enough resources!");
-                        error_text.color[3] = 1.;
-                        return;
-                    }
+        // towers the player can't afford are greyed out, and the one under
+        // the mouse is highlighted and explained by the tooltip
+        for event in clicks.iter() {
+            if let ClickEvent::Hovered(pos) = event {
+                self.hover = Some(pos.clone());
+            }
+        }
+        let hover = self.hover.clone();
+        let radius = (map.tile_width() / 2) as f32;
+        let hovered = hover.as_ref().and_then(|mouse_trans| {
+            self.shown.iter().find(|button| {
+                let trans = transforms.get(button.icon).unwrap();
+                utils::in_range(trans, radius, mouse_trans)
+            })
+        });
+        for button in self.shown.iter() {
+            let affordable = button.kind.cost() <= sim.gold();
+            if affordable {
+                tints.remove(button.icon);
+            } else {
+                let shade = Srgba::new(DISABLED_SHADE, DISABLED_SHADE, DISABLED_SHADE, 1.0);
+                tints.insert(button.icon, Tint(shade)).unwrap();
+            }
+            let is_hovered = hovered.map_or(false, |h| h.icon == button.icon);
+            sprites.get_mut(button.border).unwrap().sprite_sheet = if is_hovered {
+                hover_sheet.clone()
+            } else {
+                border_sheet.clone()
+            };
+        }
+        let tooltip = map.tooltip();
+        match (hovered, input.mouse_position()) {
+            (Some(button), Some((x, y))) => {
+                let kind = button.kind;
+                let text = &mut texts.get_mut(tooltip).unwrap().text;
+                *text = format!("{}: {} gold", kind.name(), kind.cost());
+                if kind.cost() > sim.gold() {
+                    text.push_str(" (not enough gold)");
                 }
+                // right below the mouse
+                let ui_trans = ui_transforms.get_mut(tooltip).unwrap();
+                ui_trans.local_x = x + 12.0;
+                ui_trans.local_y = -y - 12.0;
+                hidden.remove(tooltip);
             }
-            if let Some(tower) = tower {
-                let sprite = SpriteRender {
-                    sprite_sheet: handle,
-                    sprite_number: tower.sprite_number(),
-                };
-                // build our tower
-                entities
-                    .build_entity()
-                    .with(
-                        tower
-                            .pos()
-                            .to_trans(map.tile_width() as usize, map.tile_height() as usize),
-                        &mut transforms,
-                    )
-                    .with(tower, &mut towers)
-                    .with(sprite, &mut sprites)
-                    .build();
-                // remove the previous tower/build point
-                // XXX we could reuse the entity or some parts of it!
-                entities.delete(self.selected.take().unwrap()).unwrap();
+            _ => {
+                hidden.insert(tooltip, Hidden).unwrap();
             }
-            self.hide_all(&mut transforms);
-            self.selected = None;
         }
+
+        // the range of the hovered option of the build menu, else of the
+        // hovered tower, else of the selected one
+        let hovered_tower = hover.as_ref().and_then(|mouse_trans| {
+            (&points, &transforms)
+                .join()
+                .find(|(_, trans)| utils::in_range(trans, radius, mouse_trans))
+                .and_then(|(point, _)| sim.tower_at(point.pos()))
+        });
+        let range = match (hovered, self.selected) {
+            (Some(button), Some(pos)) => Some((pos, button.kind.radius())),
+            _ => hovered_tower
+                .or_else(|| self.selected.and_then(|pos| sim.tower_at(pos)))
+                .map(|tower| (tower.pos(), tower.radius())),
+        };
+        let range_sheet = self.range_sheet.clone().unwrap();
+        let circle = *self.range_circle.get_or_insert_with(|| {
+            let [r, g, b, a] = RANGE_COLOR;
+            let sprite = SpriteRender {
+                sprite_sheet: range_sheet,
+                sprite_number: 0,
+            };
+            entities
+                .build_entity()
+                .with(sprite, &mut sprites)
+                .with(Tint(Srgba::new(r, g, b, a)), &mut tints)
+                .with(Transparent, &mut transparent)
+                .with(Transform::default(), &mut transforms)
+                .with(Hidden, &mut hidden)
+                .build()
+        });
+        match range {
+            Some((pos, range)) => {
+                // the image is scaled so its diameter is that of the range
+                let center = sim.level().center(pos);
+                let trans = transforms.get_mut(circle).unwrap();
+                trans.set_translation_xyz(center.x, center.y, render::RANGES);
+                let scale = 2.0 * range / RANGE_IMAGE_SIZE as f32;
+                trans.set_scale([scale, scale, 1.0].into());
+                hidden.remove(circle);
+            }
+            None => {
+                hidden.insert(circle, Hidden).unwrap();
+            }
+        }
+
+        let clicked = clicks.into_iter().rev().find_map(|event| match event {
+            ClickEvent::Clicked(pos) => Some(pos),
+            _ => None,
+        });
+        let mouse_trans = match clicked {
+            Some(mouse_trans) => mouse_trans,
+            None => return,
+        };
+        // check if we clicked on any build points
+        let clicked_point = (&points, &transforms)
+            .join()
+            .find(|(_, trans)| utils::in_range(trans, radius, &mouse_trans))
+            .map(|(point, trans)| (point.pos(), trans.clone()));
+        if let Some((pos, trans)) = clicked_point {
+            // mark the build point (or the tower on it) as the currently selected one
+            self.selected = Some(pos);
+            // show the tower upgrade menu, or the selector on empty build points
+            let tower = sim.tower_at(pos).map(|t| t.kind());
+            let storages = (&mut sprites, &mut transforms, &mut hidden);
+            self.show_menu(tower, &trans, map, &entities, storages);
+            return;
+        }
+
+        // did we click on a button?
+        let clicked = self
+            .shown
+            .iter()
+            .find(|button| {
+                let trans = transforms.get(button.icon).unwrap();
+                utils::in_range(trans, radius, &mouse_trans)
+            })
+            .map(|button| button.kind);
+        if let (Some(pos), Some(kind)) = (self.selected, clicked) {
+            let command = if sim.tower_at(pos).is_some() {
+                Command::Upgrade { pos, kind }
+            } else {
+                Command::Build { pos, kind }
+            };
+            if let Err(err) = sim.apply(command) {
+                hud.single_write(HudEvent::Message(err.to_string()));
+                // we don't want to hide the menu selector!
+                if err == CommandError::NotEnoughGold {
+                    return;
+                }
+            }
+        }
+        self.hide_all(&mut hidden);
+        self.selected = None;
     }
 }
 
+/// The sprite of missles, which can be animated in the tileset.
+const MISSLE_SPRITE: usize = 143;
+
+/// Keeps an entity around for each missle of the simulation.
 #[derive(SystemDesc)]
-pub struct MissleSystem;
+pub struct MissleSystem {
+    missles: HashMap<Id, Entity>,
+}
+
+impl MissleSystem {
+    pub fn new() -> Self {
+        Self {
+            missles: Default::default(),
+        }
+    }
+}
 
 impl<'s> System<'s> for MissleSystem {
     type SystemData = (
-        WriteStorage<'s, Map>,
+        Option<Read<'s, Simulation>>,
+        ReadStorage<'s, Map>,
         WriteStorage<'s, Transform>,
-        WriteStorage<'s, Runner>,
-        WriteStorage<'s, Missle>,
-        Read<'s, Time>,
-        WriteStorage<'s, UiText>,
+        WriteStorage<'s, SpriteRender>,
+        WriteStorage<'s, Animation>,
         Read<'s, GameState>,
         Entities<'s>,
     );
 
     fn run(
         &mut self,
-        (mut map, mut transforms, mut runners, mut missles, time, mut texts, state, entities): Self::SystemData,
+        (sim, map, mut transforms, mut sprites, mut animations, state, entities): Self::SystemData,
     ) {
         if *state != GameState::Game {
             return;
         }
-        let map = (&mut map).join().next().unwrap();
-        let time = time.delta_seconds();
-        for (missle, ent) in (&mut missles, &entities).join() {
-            let target_ent = entities.entity(missle.target());
-            let (mut norm, is_close) = {
-                let target_tr = if let Some(trans) = transforms.get(target_ent) {
-                    trans
-                } else {
-                    // our missle's target is gone, well we are going away as well
-                    entities.delete(ent).unwrap();
-                    continue;
-                };
-                let trans = transforms.get(ent).unwrap();
-                (
-                    utils::normalize(trans, &target_tr),
-                    utils::in_range(trans, 8.0, &target_tr),
-                )
-            };
-            if is_close {
-                let runner = if let Some(runner) = runners.get_mut(target_ent) {
-                    runner
-                } else {
-                    // our missle's target is gone, well we are going away as well
-                    entities.delete(ent).unwrap();
-                    continue;
+        let sim = match sim {
+            Some(sim) => sim,
+            None => return,
+        };
+        let map = (&map).join().next().unwrap();
+        // missles which hit their target or lost it
+        let flying: HashSet<Id> = sim.missles().iter().map(|m| m.id()).collect();
+        self.missles.retain(|id, ent| {
+            if !flying.contains(id) {
+                entities.delete(*ent).unwrap();
+            }
+            flying.contains(id)
+        });
+        for missle in sim.missles() {
+            let ent = *self.missles.entry(missle.id()).or_insert_with(|| {
+                let sprite_render = SpriteRender {
+                    sprite_sheet: map.sprite_sheet_handle(),
+                    sprite_number: MISSLE_SPRITE,
                 };
-                runner.deal_damage(missle.damage());
-                runner.apply_debuff(missle.debuff());
-                entities.delete(ent).unwrap();
-                if runner.hp() <= 0.0 {
-                    map.add_gold(runner.bounty());
-                    entities.delete(target_ent).unwrap();
-                    // update labels as well
-                    texts.get_mut(map.gold_text()).unwrap().text = format!("{} gold", map.gold());
+                let mut builder = entities.build_entity().with(sprite_render, &mut sprites);
+                if let Some(flight) = map.animation(MISSLE_SPRITE) {
+                    builder = builder.with(flight, &mut animations);
                 }
-            } else {
-                norm.x *= time * MISSLE_SPEED;
-                norm.y *= time * MISSLE_SPEED;
-                transforms.get_mut(ent).unwrap().append_translation(norm);
-            }
+                builder.build()
+            });
+            let mut trans = Transform::default();
+            trans.set_translation_xyz(
+                missle.position().x,
+                missle.position().y,
+                render::PROJECTILES,
+            );
+            transforms.insert(ent, trans).unwrap();
         }
     }
 }
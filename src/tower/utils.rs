@@ -1,10 +1,6 @@
-use amethyst::core::{
-    math::{Point3, Vector2, Vector3},
-    Transform,
-};
-use amethyst::input::{InputHandler, StringBindings};
-use amethyst::renderer::Camera;
-use amethyst::window::ScreenDimensions;
+use amethyst::core::{math::Vector3, Transform};
+
+use crate::sim::Tower;
 
 pub fn in_range(origin: &Transform, radius: f32, point: &Transform) -> bool {
     let t1 = origin.translation();
@@ -13,6 +9,30 @@ pub fn in_range(origin: &Transform, radius: f32, point: &Transform) -> bool {
     distance <= radius
 }
 
+/// What the info panel shows about a tower.
+pub fn describe(tower: &Tower) -> String {
+    let kind = tower.kind();
+    format!(
+        "{}\n\
+         Damage: {:.0} ({:.1} per second)\n\
+         Range: {:.0}\n\
+         Attacks per second: {:.1}\n\
+         Targeting the {} runner\n\
+         Damage dealt: {:.0}\n\
+         Kills: {}\n\
+         Sells for {} gold",
+        kind.name(),
+        kind.damage(),
+        kind.dps(),
+        kind.radius(),
+        1.0 / kind.speed(),
+        tower.targeting().name(),
+        tower.damage_dealt(),
+        tower.kills(),
+        tower.refund()
+    )
+}
+
 pub fn normalize(origin: &Transform, dest: &Transform) -> Vector3<f32> {
     let t1 = origin.translation();
     let t2 = dest.translation();
@@ -22,28 +42,3 @@ pub fn normalize(origin: &Transform, dest: &Transform) -> Vector3<f32> {
     norm.y = norm.y / magnitude;
     norm
 }
-
-/// Gets the mouse position after Left-Mouse-Button is pressed in terms of
-/// world coordinates.
-pub fn mouse_position(
-    input: &InputHandler<StringBindings>,
-    dim: &ScreenDimensions,
-    camera: &Camera,
-    camera_trans: &Transform,
-) -> Option<Transform> {
-    if input.mouse_button_is_down(amethyst::winit::MouseButton::Left) {
-        if let Some(m_pos) = input.mouse_position() {
-            let screen_dimensions = Vector2::new(dim.width(), dim.height());
-            let mouse_pos = Point3::new(m_pos.0, m_pos.1, 0.0);
-            let mouse_coords = camera.screen_to_world_point(
-                mouse_pos,
-                screen_dimensions,
-                &camera_trans,
-            );
-            let mut mouse_trans = Transform::default();
-            mouse_trans.set_translation_xyz(mouse_coords.coords[0], mouse_coords.coords[1], 1.0);
-            return Some(mouse_trans);
-        }
-    }
-    None
-}
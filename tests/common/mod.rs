@@ -0,0 +1,103 @@
+//! A tiny level to play the simulation on, without amethyst or a Tiled map.
+//! The Tiled maps of the tests of level loading are made in `tmx`.
+// not every test uses every helper
+#![allow(dead_code)]
+
+use tower_def::sim::{
+    load_level, Archetypes, Command, Coord, Exit, Flight, Id, Level, Road, Runner, RunnerKind,
+    Simulation, Spawn, TowerKind, Waves,
+};
+
+use std::path::Path;
+
+pub mod tmx;
+
+pub const TILE_SIZE: usize = 16;
+
+/// An 8x3 level with a straight road along the middle row, going from the
+/// spawn at (0, 1) to the exit at (7, 1). Fliers take the same way. Towers
+/// can be built at (3, 0), (3, 2) and (5, 2).
+pub fn level() -> Level {
+    let road: Vec<Coord> = (0..8).map(|x| Coord::new(x, 1)).collect();
+    let flight = vec![
+        Coord::new(0, 1).center(TILE_SIZE, TILE_SIZE),
+        Coord::new(7, 1).center(TILE_SIZE, TILE_SIZE),
+    ];
+    Level::new(
+        (8, 3),
+        (TILE_SIZE, TILE_SIZE),
+        vec![Road::new(0, 0, road)],
+        vec![Flight::new(0, 0, flight)],
+        vec![Spawn::new(0, Coord::new(0, 1))],
+        vec![Exit::new(0, Coord::new(7, 1), 1)],
+        vec![Coord::new(3, 0), Coord::new(3, 2), Coord::new(5, 2)],
+    )
+}
+
+/// A game of the level at `path`, with its own waves and runners, as the
+/// game would start it.
+pub fn game(path: &Path, seed: u64) -> Simulation {
+    let (_, level) = load_level(path).unwrap();
+    let waves = Waves::for_level(path, &level).unwrap();
+    let archetypes = Archetypes::for_level(path).unwrap();
+    Simulation::new(level, waves, seed).with_archetypes(archetypes)
+}
+
+/// A game on `level()` without any waves, so runners only come in when a
+/// test spawns them.
+pub struct Harness {
+    sim: Simulation,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Self::on(level())
+    }
+
+    /// A game on another level.
+    pub fn on(level: Level) -> Self {
+        Self {
+            sim: Simulation::new(level, Waves::new(vec![]), 0),
+        }
+    }
+
+    pub fn sim(&self) -> &Simulation {
+        &self.sim
+    }
+
+    pub fn sim_mut(&mut self) -> &mut Simulation {
+        &mut self.sim
+    }
+
+    pub fn build(&mut self, x: usize, y: usize, kind: TowerKind) {
+        let pos = Coord::new(x, y);
+        self.sim.apply(Command::Build { pos, kind }).unwrap();
+    }
+
+    pub fn spawn(&mut self, kind: RunnerKind) -> Id {
+        self.sim.spawn(kind, Some(0)).unwrap()
+    }
+
+    /// Makes `ticks` steps of the simulation.
+    pub fn advance(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.sim.step();
+        }
+    }
+
+    /// Steps until `done` holds, failing the test if it takes more than
+    /// `ticks` steps.
+    pub fn advance_until(&mut self, ticks: u64, done: impl Fn(&Simulation) -> bool) {
+        for _ in 0..ticks {
+            if done(&self.sim) {
+                return;
+            }
+            self.sim.step();
+        }
+        assert!(done(&self.sim), "still waiting after {} ticks", ticks);
+    }
+
+    pub fn runner(&self, id: Id) -> &Runner {
+        self.sim.runner(id).expect("the runner is gone")
+    }
+}
@@ -0,0 +1,114 @@
+//! Tiled maps drawn with characters, e.g. `["S---E"]` is a straight road
+//! from a spawn to an exit.
+
+/// The tiles of the maps, and the properties which give them meaning.
+pub const TILES: [(char, &str); 8] = [
+    ('.', ""),
+    (
+        'S',
+        r#"<property name="start-point" type="bool" value="true"/>"#,
+    ),
+    (
+        'E',
+        r#"<property name="end-point" type="bool" value="true"/>"#,
+    ),
+    (
+        '-',
+        r#"<property name="road" type="bool" value="true"/>
+             <property name="right" type="bool" value="true"/>
+             <property name="left" type="bool" value="true"/>"#,
+    ),
+    (
+        'v',
+        r#"<property name="road" type="bool" value="true"/>
+             <property name="down" type="bool" value="true"/>"#,
+    ),
+    (
+        'T',
+        r#"<property name="road" type="bool" value="true"/>
+             <property name="right" type="bool" value="true"/>
+             <property name="down" type="bool" value="true"/>
+             <property name="left" type="bool" value="true"/>"#,
+    ),
+    ('x', r#"<property name="road" type="bool" value="true"/>"#),
+    (
+        'B',
+        r#"<property name="construction-point" type="bool" value="true"/>"#,
+    ),
+];
+
+/// A Tiled map with a tile for each character of `rows`, from the top down.
+pub fn tmx(rows: &[&str]) -> String {
+    tmx_from(rows, 1)
+}
+
+/// Like `tmx`, but the tiles are in a tileset starting at `first_gid`, after
+/// a tileset of grass.
+pub fn tmx_from(rows: &[&str], first_gid: u32) -> String {
+    let (width, height) = (rows[0].len(), rows.len());
+    let mut tiles = String::new();
+    for (id, (_, properties)) in TILES.iter().enumerate() {
+        if !properties.is_empty() {
+            tiles.push_str(&format!(
+                "<tile id=\"{}\"><properties>{}</properties></tile>\n",
+                id, properties
+            ));
+        }
+    }
+    let data: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let gids: Vec<String> = row
+                .chars()
+                .map(|c| {
+                    let id = TILES.iter().position(|(t, _)| *t == c).unwrap() as u32;
+                    (first_gid + id).to_string()
+                })
+                .collect();
+            gids.join(",")
+        })
+        .collect();
+    let grass = if first_gid > 1 {
+        format!(
+            r#"<tileset firstgid="1" name="grass" tilewidth="16" tileheight="16" tilecount="{n}" columns="{n}">
+  <image source="grass.png" width="{w}" height="16"/>
+ </tileset>"#,
+            n = first_gid - 1,
+            w = (first_gid - 1) * 16
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="orthogonal" renderorder="right-down" width="{w}" height="{h}" tilewidth="16" tileheight="16" infinite="0">
+ {grass}
+ <tileset firstgid="{first_gid}" name="tiles" tilewidth="16" tileheight="16" tilecount="8" columns="8">
+  <image source="tiles.png" width="128" height="16"/>
+  {tiles}
+ </tileset>
+ <layer id="1" name="ground" width="{w}" height="{h}">
+  <data encoding="csv">
+{data}
+</data>
+ </layer>
+</map>"#,
+        w = width,
+        h = height,
+        grass = grass,
+        first_gid = first_gid,
+        tiles = tiles,
+        data = data.join(",\n")
+    )
+}
+
+/// Adds an object layer with `objects` to a map made by `tmx`.
+pub fn with_objects(tmx: &str, objects: &str) -> String {
+    tmx.replace(
+        "</map>",
+        &format!(
+            "<objectgroup id=\"2\" name=\"objects\">\n{}\n</objectgroup>\n</map>",
+            objects
+        ),
+    )
+}
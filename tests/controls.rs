@@ -0,0 +1,46 @@
+use amethyst::input::{Bindings, Button, StringBindings};
+use amethyst::winit::{MouseButton, VirtualKeyCode};
+use tower_def::controls::{describe, rebind, ACTIONS};
+
+fn default_bindings() -> Bindings<StringBindings> {
+    let ron = std::fs::read_to_string("config/bindings.ron").unwrap();
+    ron::de::from_str(&ron).unwrap()
+}
+
+#[test]
+fn every_action_is_bound() {
+    let bindings = default_bindings();
+    for (action, _) in ACTIONS.iter() {
+        assert_ne!(describe(&bindings, action), "nothing", "{}", action);
+    }
+}
+
+#[test]
+fn rebinding_takes_the_button_from_other_actions() {
+    let mut bindings = default_bindings();
+    rebind(&mut bindings, "pause", Button::Key(VirtualKeyCode::S)).unwrap();
+    assert_eq!(describe(&bindings, "pause"), "S");
+    assert_eq!(describe(&bindings, "pan-down"), "Down");
+}
+
+#[test]
+fn select_and_cancel_keep_a_binding() {
+    let mut bindings = default_bindings();
+    let left = Button::Mouse(MouseButton::Left);
+    assert_eq!(rebind(&mut bindings, "pause", left), Err("select"));
+    assert_eq!(describe(&bindings, "select"), "Mouse Left");
+    assert_eq!(describe(&bindings, "pause"), "Space");
+    rebind(&mut bindings, "select", Button::Key(VirtualKeyCode::Return)).unwrap();
+    rebind(&mut bindings, "pause", left).unwrap();
+    assert_eq!(describe(&bindings, "pause"), "Mouse Left");
+}
+
+#[test]
+fn actions_can_be_bound_without_select() {
+    let mut bindings = default_bindings();
+    bindings.remove_action_binding("select", &[Button::Mouse(MouseButton::Left)]);
+    assert_eq!(describe(&bindings, "select"), "nothing");
+    rebind(&mut bindings, "pause", Button::Key(VirtualKeyCode::P)).unwrap();
+    rebind(&mut bindings, "select", Button::Mouse(MouseButton::Left)).unwrap();
+    assert_eq!(describe(&bindings, "select"), "Mouse Left");
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::game;
+use tower_def::sim::Bot;
+
+use std::path::PathBuf;
+
+/// Games which take longer than this (an hour) count as lost.
+const MAX_TICKS: u64 = 60 * 60 * 60;
+
+/// The levels the game comes with.
+fn levels() -> Vec<PathBuf> {
+    let mut levels: Vec<PathBuf> = std::fs::read_dir("assets")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "tmx"))
+        .collect();
+    levels.sort();
+    levels
+}
+
+#[test]
+fn the_bot_clears_every_level() {
+    let levels = levels();
+    assert!(!levels.is_empty());
+    for path in levels {
+        // a few seeds, as runners take other roads with each of them
+        for seed in 0..5 {
+            let mut sim = game(&path, seed);
+            let bot = Bot::new(sim.level());
+            bot.play(&mut sim, MAX_TICKS);
+            assert!(
+                sim.is_won(),
+                "the bot couldn't clear {} with seed {}",
+                path.display(),
+                seed
+            );
+        }
+    }
+}
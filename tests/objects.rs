@@ -0,0 +1,132 @@
+use tower_def::sim::{Coord, Level, Point, RunnerKind, Spawn};
+
+mod common;
+use common::{
+    tmx::{tmx, with_objects},
+    Harness,
+};
+
+fn level(rows: &[&str], objects: &str) -> Level {
+    let map = tiled::parse(with_objects(&tmx(rows), objects).as_bytes()).unwrap();
+    Level::from_tiled(&map).unwrap()
+}
+
+fn close(a: Point, b: Point) -> bool {
+    a.distance(&b) < 0.01
+}
+
+// a road bending around the middle of the map, from the spawn at the top left
+// to the exit at the bottom right
+const BEND: &str = r#"<object id="1" type="road" x="8" y="8">
+  <polyline points="0,0 64,0 64,32"/>
+</object>"#;
+
+#[test]
+fn roads_can_be_drawn_as_polylines() {
+    let level = level(&["S....", ".....", "....E"], BEND);
+    assert_eq!(level.roads().len(), 1);
+    let road = &level.roads()[0];
+    let points = road.points();
+    // the curve goes through each point of the line
+    assert!(close(points[0], Point::new(8., 40.)));
+    assert!(points.iter().any(|p| close(*p, Point::new(72., 40.))));
+    assert!(close(*points.last().unwrap(), Point::new(72., 8.)));
+    // rounding the corner without leaving the map
+    assert!(points.len() > 3);
+    assert!(points
+        .iter()
+        .all(|p| p.x >= 0. && p.x < 80. && p.y >= 0. && p.y < 48.));
+    assert_eq!(road.tiles().first(), Some(&Coord::new(0, 2)));
+    assert_eq!(road.tiles().last(), Some(&Coord::new(4, 0)));
+}
+
+#[test]
+fn roads_can_be_left_unsmoothed() {
+    let road = BEND.replace(
+        "<polyline",
+        r#"<properties><property name="smooth" type="bool" value="false"/></properties>
+  <polyline"#,
+    );
+    let level = level(&["S....", ".....", "....E"], &road);
+    assert_eq!(
+        level.roads()[0].points(),
+        &vec![
+            Point::new(8., 40.),
+            Point::new(72., 40.),
+            Point::new(72., 8.)
+        ]
+    );
+}
+
+#[test]
+fn runners_follow_polyline_roads() {
+    let mut game = Harness::on(level(&["S....", ".....", "....E"], BEND));
+    let id = game.spawn(RunnerKind::Grunt);
+    game.advance_until(200, |sim| {
+        sim.runner(id).map_or(false, |r| r.position().x > 70.)
+    });
+    // at the corner, before heading down to the exit
+    assert!(game.runner(id).position().y > 30.);
+    game.advance_until(200, |sim| sim.lives() < 20);
+}
+
+#[test]
+fn leaks_count_runners_rather_than_lives() {
+    let exit = r#"<object id="1" type="exit" x="76" y="4">
+  <properties><property name="leak-penalty" type="int" value="3"/></properties>
+  <point/>
+</object>"#;
+    let mut game = Harness::on(level(&["S----"], exit));
+    game.spawn(RunnerKind::Grunt);
+    game.advance_until(600, |sim| sim.lives() < 20);
+    assert_eq!(game.sim().lives(), 17);
+    assert_eq!(game.sim().stats().leaked(0), 1);
+}
+
+#[test]
+fn spawns_and_exits_can_be_points() {
+    let objects = r#"<object id="1" type="spawn" x="4" y="4">
+  <properties><property name="spawn-id" type="int" value="2"/></properties>
+  <point/>
+</object>
+<object id="2" type="exit" x="76" y="4">
+  <properties><property name="leak-penalty" type="int" value="3"/></properties>
+  <point/>
+</object>"#;
+    let level = level(&["-----"], objects);
+    assert_eq!(level.spawns(), &vec![Spawn::new(2, Coord::new(0, 0))]);
+    assert_eq!(level.exits()[0].pos(), Coord::new(4, 0));
+    assert_eq!(level.exits()[0].penalty(), 3);
+    assert_eq!(level.roads().len(), 1);
+}
+
+#[test]
+fn unnamed_spawns_come_after_named_ones() {
+    let objects = r#"<object id="1" type="spawn" x="4" y="20">
+  <properties><property name="spawn-id" type="int" value="0"/></properties>
+  <point/>
+</object>"#;
+    let level = level(&["S---E", "----E"], objects);
+    let mut ids: Vec<usize> = level.spawns().iter().map(|s| s.id()).collect();
+    ids.sort();
+    assert_eq!(ids, vec![0, 1]);
+}
+
+#[test]
+fn build_zones() {
+    // the whole middle row, except for its center
+    let objects = r#"<object id="1" type="build-zone" x="0" y="16" width="80" height="16"/>
+<object id="2" type="no-build" x="32" y="16" width="16" height="16"/>"#;
+    let level = level(&["S---E", ".....", "....."], objects);
+    let mut build_points = level.build_points().clone();
+    build_points.sort_by_key(|c| c.x);
+    assert_eq!(
+        build_points,
+        vec![
+            Coord::new(0, 1),
+            Coord::new(1, 1),
+            Coord::new(3, 1),
+            Coord::new(4, 1)
+        ]
+    );
+}
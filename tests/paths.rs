@@ -0,0 +1,63 @@
+use tower_def::sim::{gather_paths, Coord};
+
+const UP: u8 = 0b0001;
+const RIGHT: u8 = 0b0010;
+const DOWN: u8 = 0b0100;
+const LEFT: u8 = 0b1000;
+const ANY: u8 = UP | RIGHT | DOWN | LEFT;
+
+#[test]
+fn straight_road() {
+    // indexed by [x][y]
+    let map = vec![vec![ANY], vec![RIGHT | LEFT], vec![RIGHT | LEFT], vec![ANY]];
+    let paths = gather_paths(vec![Coord::new(0, 0)], &[Coord::new(3, 0)], &map);
+    let expected: Vec<Coord> = (0..4).map(|x| Coord::new(x, 0)).collect();
+    assert_eq!(paths, vec![expected]);
+}
+
+#[test]
+fn forks_give_a_path_each() {
+    // the road splits around (1, 1) and joins again at the exit
+    let map = vec![
+        vec![RIGHT, ANY, RIGHT],
+        vec![RIGHT, 0, RIGHT],
+        vec![UP, ANY, DOWN],
+    ];
+    let mut paths = gather_paths(vec![Coord::new(0, 1)], &[Coord::new(2, 1)], &map);
+    paths.sort_by_key(|p| p[1].y);
+    assert_eq!(
+        paths,
+        vec![
+            vec![
+                Coord::new(0, 1),
+                Coord::new(0, 0),
+                Coord::new(1, 0),
+                Coord::new(2, 0),
+                Coord::new(2, 1),
+            ],
+            vec![
+                Coord::new(0, 1),
+                Coord::new(0, 2),
+                Coord::new(1, 2),
+                Coord::new(2, 2),
+                Coord::new(2, 1),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn roads_go_one_way() {
+    // the road only goes left, away from the exit
+    let map = vec![vec![ANY], vec![LEFT], vec![ANY]];
+    let paths = gather_paths(vec![Coord::new(0, 0)], &[Coord::new(2, 0)], &map);
+    assert!(paths.is_empty());
+}
+
+#[test]
+fn loops_dont_go_on_forever() {
+    // runners can go round in circles, but never reach the exit
+    let map = vec![vec![ANY, ANY], vec![ANY, ANY]];
+    let paths = gather_paths(vec![Coord::new(0, 0)], &[Coord::new(5, 5)], &map);
+    assert!(paths.is_empty());
+}
@@ -0,0 +1,89 @@
+use tower_def::sim::{Ability, Archetype, Archetypes, Debuff, Phase, Point, Runner, RunnerKind};
+
+fn runner(kind: RunnerKind) -> Runner {
+    let archetypes = Archetypes::default();
+    Runner::new(0, kind, archetypes.get(kind), 0, 0, 0, Point::new(0.0, 0.0))
+}
+
+#[test]
+fn debuffs_wear_off() {
+    let mut runner = runner(RunnerKind::Grunt);
+    runner.apply_debuff(Some(Debuff::new(0.5, 1.0)));
+    assert_eq!(runner.speed(), 16.0);
+    runner.tick(0.5);
+    assert_eq!(runner.speed(), 16.0);
+    runner.tick(0.6);
+    assert!(runner.debuffs().is_empty());
+    assert_eq!(runner.speed(), 32.0);
+}
+
+#[test]
+fn debuffs_stack() {
+    let mut runner = runner(RunnerKind::Grunt);
+    runner.apply_debuff(Some(Debuff::new(0.5, 1.0)));
+    runner.apply_debuff(Some(Debuff::new(0.5, 2.0)));
+    assert_eq!(runner.speed(), 8.0);
+    runner.tick(1.5);
+    assert_eq!(runner.speed(), 16.0);
+}
+
+#[test]
+fn shields_absorb_damage_first() {
+    let mut runner = runner(RunnerKind::Knight);
+    let shield = runner.shield();
+    assert!(shield > 0.0);
+    runner.deal_damage(shield + 10.0);
+    assert_eq!(runner.shield(), 0.0);
+    assert_eq!(runner.hp(), runner.max_hp() - 10.0);
+}
+
+#[test]
+fn healing_stops_at_max_hp() {
+    let mut runner = runner(RunnerKind::Grunt);
+    runner.deal_damage(30.0);
+    runner.heal(20.0);
+    assert_eq!(runner.hp(), 90.0);
+    runner.heal(20.0);
+    assert_eq!(runner.hp(), 100.0);
+}
+
+#[test]
+fn abilities_come_from_the_archetype() {
+    let archetype = Archetype::new(
+        vec![Ability::Stealth, Ability::Shield { amount: 20.0 }],
+        vec![],
+    );
+    let origin = Point::new(0.0, 0.0);
+    let grunt = Runner::new(0, RunnerKind::Grunt, &archetype, 0, 0, 0, origin);
+    assert!(grunt.is_stealthy());
+    assert_eq!(grunt.shield(), 20.0);
+    // the bundled archetypes only give stealth to rogues
+    assert!(runner(RunnerKind::Rogue).is_stealthy());
+    assert!(!runner(RunnerKind::Grunt).is_stealthy());
+}
+
+#[test]
+fn phases_come_from_the_archetype() {
+    let phase = Phase {
+        hp: 0.5,
+        speed: 2.0,
+        summon: Some((RunnerKind::Splitling, 2)),
+        immunity: 0.0,
+        abilities: vec![Ability::Stealth],
+        sprite: Some(7),
+    };
+    let archetype = Archetype::new(vec![], vec![phase.clone()]);
+    let origin = Point::new(0.0, 0.0);
+    let mut boss = Runner::new(0, RunnerKind::Grunt, &archetype, 0, 0, 0, origin);
+    assert!(boss.is_boss());
+    boss.deal_damage(40.0);
+    assert_eq!(boss.advance_phase(), None);
+    boss.deal_damage(10.0);
+    assert_eq!(boss.advance_phase(), Some(phase));
+    assert_eq!(boss.speed(), 64.0);
+    assert!(boss.is_stealthy());
+    assert_eq!(boss.sprite_number(), 7);
+    // each phase only starts once
+    assert_eq!(boss.advance_phase(), None);
+    assert!(runner(RunnerKind::Warlord).is_boss());
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{game, Harness};
+use tower_def::sim::{load_level, Command, SaveError, SaveGame, TowerKind, SAVE_VERSION};
+
+use std::path::{Path, PathBuf};
+
+const LEVEL: &str = "assets/tower-def.tmx";
+
+/// Where a test can save a game, without getting in the way of the others.
+fn save_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("tower-def-saves");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+#[test]
+fn saved_games_continue_where_they_stopped() {
+    let mut sim = game(Path::new(LEVEL), 5);
+    sim.apply(Command::SetSpeed { speed: 2 }).unwrap();
+    for _ in 0..600 {
+        sim.step();
+    }
+    let path = save_path("continue.ron");
+    SaveGame::new(PathBuf::from(LEVEL), &sim)
+        .save(&path)
+        .unwrap();
+
+    let save = SaveGame::load(&path).unwrap();
+    assert_eq!(save.level(), Path::new(LEVEL));
+    let (_, level) = load_level(save.level()).unwrap();
+    let mut loaded = save.into_simulation(level);
+    assert_eq!(loaded.checksum(), sim.checksum());
+    assert_eq!(loaded.history(), sim.history());
+    assert_eq!(loaded.speed(), 2);
+    // and both go on the same way
+    for _ in 0..600 {
+        sim.step();
+        loaded.step();
+    }
+    assert_eq!(loaded.checksum(), sim.checksum());
+}
+
+#[test]
+fn saves_of_other_versions_are_refused() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Simple);
+    let path = save_path("old.ron");
+    SaveGame::new(PathBuf::from(LEVEL), harness.sim())
+        .save(&path)
+        .unwrap();
+    let save = std::fs::read_to_string(&path).unwrap();
+    let current = format!("version: {}", SAVE_VERSION);
+    assert!(save.contains(&current));
+    let old = save.replace(&current, &format!("version: {}", SAVE_VERSION - 1));
+    std::fs::write(&path, old).unwrap();
+    match SaveGame::load(&path) {
+        Err(SaveError::Version(version)) => assert_eq!(version, SAVE_VERSION - 1),
+        other => panic!("expected a version error, got {:?}", other),
+    }
+}
@@ -0,0 +1,284 @@
+mod common;
+
+use common::{game, level, Harness};
+use tower_def::sim::{
+    Bot, Command, CommandError, Coord, Replay, RunnerKind, Simulation, Targeting, TowerKind, Wave,
+    WaveGroup, Waves,
+};
+
+use std::path::{Path, PathBuf};
+
+#[test]
+fn towers_shoot_runners_in_range() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Simple);
+    let runner = harness.spawn(RunnerKind::Grunt);
+    harness.advance_until(120, |sim| !sim.missles().is_empty());
+    assert_eq!(harness.sim().missles().len(), 1);
+    assert_eq!(harness.sim().missles()[0].target(), runner);
+    harness.advance_until(60, |sim| sim.missles().is_empty());
+    assert_eq!(harness.runner(runner).hp(), 80.0);
+}
+
+#[test]
+fn towers_dont_shoot_runners_out_of_range() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Simple);
+    harness.spawn(RunnerKind::Grunt);
+    harness.advance(10);
+    assert!(harness.sim().missles().is_empty());
+}
+
+#[test]
+fn killing_runners_gives_gold() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Simple);
+    let gold = harness.sim().gold();
+    let runner = harness.spawn(RunnerKind::Splitling);
+    harness.advance_until(300, |sim| sim.runner(runner).is_none());
+    assert_eq!(harness.sim().lives(), 20);
+    assert_eq!(harness.sim().gold(), gold + RunnerKind::Splitling.bounty());
+}
+
+#[test]
+fn towers_keep_track_of_their_hits() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Simple);
+    let runner = harness.spawn(RunnerKind::Splitling);
+    harness.advance_until(300, |sim| sim.runner(runner).is_none());
+    let tower = harness.sim().tower_at(Coord::new(3, 2)).unwrap();
+    // the second missle only takes the hp the runner had left
+    assert_eq!(tower.damage_dealt(), RunnerKind::Splitling.hp());
+    assert_eq!(tower.kills(), 1);
+}
+
+#[test]
+fn damage_absorbed_by_shields_isnt_dealt() {
+    let mut harness = Harness::new();
+    harness.build(3, 0, TowerKind::Simple);
+    harness.build(3, 2, TowerKind::Simple);
+    let runner = harness.spawn(RunnerKind::Knight);
+    // once it is past the towers
+    harness.advance_until(600, |sim| sim.runner(runner).unwrap().pos() >= 6);
+    let knight = harness.runner(runner);
+    assert_eq!(knight.shield(), 0.0);
+    let dealt: f32 = harness
+        .sim()
+        .towers()
+        .iter()
+        .map(|t| t.damage_dealt())
+        .sum();
+    assert!(dealt > 0.0);
+    assert_eq!(dealt, knight.max_hp() - knight.hp());
+}
+
+#[test]
+fn frost_towers_slow_runners_down() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Frost);
+    let runner = harness.spawn(RunnerKind::Grunt);
+    harness.advance_until(120, |sim| !sim.missles().is_empty());
+    harness.advance_until(60, |sim| sim.missles().is_empty());
+    let runner = harness.runner(runner);
+    assert_eq!(runner.hp(), 90.0);
+    assert_eq!(runner.debuffs().len(), 1);
+    assert_eq!(runner.speed(), RunnerKind::Grunt.speed() / 2.0);
+}
+
+#[test]
+fn frost_towers_cant_shoot_fliers() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Frost);
+    let flier = harness.spawn(RunnerKind::Flier);
+    harness.advance_until(600, |sim| {
+        assert!(sim.missles().is_empty());
+        sim.runner(flier).is_none()
+    });
+    assert_eq!(harness.sim().lives(), 19);
+}
+
+#[test]
+fn runners_reaching_the_exit_cost_lives() {
+    let mut harness = Harness::new();
+    let runner = harness.spawn(RunnerKind::Grunt);
+    harness.advance_until(600, |sim| sim.runner(runner).is_none());
+    assert_eq!(harness.sim().lives(), 19);
+    assert_eq!(harness.sim().stats().leaked(0), 1);
+    assert!(harness.sim().is_won());
+}
+
+#[test]
+fn killed_runners_are_told_apart_from_leaks() {
+    let mut harness = Harness::new();
+    let leaked = harness.spawn(RunnerKind::Grunt);
+    harness.advance_until(600, |sim| sim.runner(leaked).is_none());
+    assert!(!harness.sim().was_killed(leaked));
+    assert_eq!(harness.sim().score(), 0);
+    harness.build(3, 2, TowerKind::Simple);
+    let killed = harness.spawn(RunnerKind::Splitling);
+    harness.advance_until(300, |sim| sim.runner(killed).is_none());
+    assert!(harness.sim().was_killed(killed));
+    // kills are worth their bounty
+    assert_eq!(harness.sim().score(), RunnerKind::Splitling.bounty());
+    // they're only remembered for a while
+    harness.advance(120);
+    assert!(!harness.sim().was_killed(killed));
+}
+
+#[test]
+fn building_needs_gold_and_a_free_build_point() {
+    let mut harness = Harness::new();
+    let sim = harness.sim_mut();
+    let build = |x, y, kind| Command::Build {
+        pos: Coord::new(x, y),
+        kind,
+    };
+    // on the road
+    assert_eq!(
+        sim.apply(build(3, 1, TowerKind::Simple)),
+        Err(CommandError::InvalidTarget)
+    );
+    // turrets are upgrades only
+    assert_eq!(
+        sim.apply(build(3, 2, TowerKind::Turret)),
+        Err(CommandError::InvalidTarget)
+    );
+    assert_eq!(sim.apply(build(3, 2, TowerKind::Simple)), Ok(()));
+    assert_eq!(
+        sim.apply(build(3, 2, TowerKind::Simple)),
+        Err(CommandError::InvalidTarget)
+    );
+    assert_eq!(sim.apply(build(3, 0, TowerKind::Simple)), Ok(()));
+    assert_eq!(
+        sim.apply(build(5, 2, TowerKind::Simple)),
+        Err(CommandError::NotEnoughGold)
+    );
+    assert_eq!(sim.gold(), 0);
+}
+
+#[test]
+fn selling_refunds_half_the_cost() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Simple);
+    let pos = Coord::new(3, 2);
+    let sim = harness.sim_mut();
+    assert_eq!(sim.apply(Command::Sell { pos }), Ok(()));
+    assert!(sim.tower_at(pos).is_none());
+    assert_eq!(sim.gold(), 75);
+    // what it did is still part of the stats
+    assert_eq!(sim.stats().sold().len(), 1);
+    assert_eq!(sim.stats().sold()[0].pos(), pos);
+}
+
+#[test]
+fn upgrades_keep_the_targeting() {
+    let mut harness = Harness::new();
+    harness.build(3, 2, TowerKind::Simple);
+    let pos = Coord::new(3, 2);
+    let targeting = Targeting::Weakest;
+    let sim = harness.sim_mut();
+    sim.apply(Command::SetTargeting { pos, targeting }).unwrap();
+    let upgrade = Command::Upgrade {
+        pos,
+        kind: TowerKind::Turret,
+    };
+    assert_eq!(sim.apply(upgrade), Err(CommandError::NotEnoughGold));
+    // earn enough for the upgrade
+    while harness.sim().gold() < TowerKind::Turret.cost() {
+        let runner = harness.spawn(RunnerKind::Splitling);
+        harness.advance_until(300, |sim| sim.runner(runner).is_none());
+    }
+    let sim = harness.sim_mut();
+    assert_eq!(sim.apply(upgrade), Ok(()));
+    let tower = sim.tower_at(pos).unwrap();
+    assert_eq!(tower.kind(), TowerKind::Turret);
+    assert_eq!(tower.targeting(), targeting);
+    // as well as what the tower did
+    assert!(tower.kills() > 0);
+}
+
+#[test]
+fn the_next_wave_can_be_called_early() {
+    let wave = Wave::new(5.0, vec![WaveGroup::new(RunnerKind::Grunt, 1, 1.0, None)]);
+    let mut sim = Simulation::new(level(), Waves::new(vec![wave]), 0);
+    assert_eq!(sim.waves().time_until_next(), Some(5.0));
+    assert_eq!(sim.apply(Command::NextWave), Ok(()));
+    assert_eq!(sim.waves().time_until_next(), None);
+    // calling it early is worth points
+    assert_eq!(sim.score(), 10);
+    // it already started
+    assert_eq!(
+        sim.apply(Command::NextWave),
+        Err(CommandError::InvalidTarget)
+    );
+    for _ in 0..61 {
+        sim.step();
+    }
+    assert_eq!(sim.runners().len(), 1);
+}
+
+#[test]
+fn speed_zero_pauses_the_game() {
+    let mut harness = Harness::new();
+    let sim = harness.sim_mut();
+    assert_eq!(sim.apply(Command::SetSpeed { speed: 0 }), Ok(()));
+    assert_eq!(sim.speed(), 0);
+    assert_eq!(
+        sim.apply(Command::SetSpeed { speed: 4 }),
+        Err(CommandError::InvalidTarget)
+    );
+}
+
+const LEVEL: &str = "assets/tower-def.tmx";
+
+/// Lets the bot play the first minute of a game of the bundled level.
+fn play(seed: u64) -> Simulation {
+    let mut sim = game(Path::new(LEVEL), seed);
+    let bot = Bot::new(sim.level());
+    for _ in 0..60 * 60 {
+        bot.act(&mut sim);
+        sim.step();
+    }
+    sim
+}
+
+#[test]
+fn games_with_the_same_seed_are_the_same() {
+    let (a, b) = (play(7), play(7));
+    assert!(!a.history().is_empty());
+    assert_eq!(a.history(), b.history());
+    assert_eq!(a.checksum(), b.checksum());
+}
+
+#[test]
+fn games_with_other_seeds_differ() {
+    assert_ne!(play(7).checksum(), play(8).checksum());
+}
+
+/// Plays `replay` back up to where its recording stopped.
+fn play_back(replay: &Replay) -> Simulation {
+    let mut sim = game(replay.level(), replay.seed());
+    sim.play(replay.commands().clone());
+    while sim.tick() < replay.ticks() {
+        sim.step();
+    }
+    sim
+}
+
+#[test]
+fn replays_play_the_same_game_again() {
+    let replay = Replay::record(PathBuf::from(LEVEL), &play(3));
+    let sim = play_back(&replay);
+    assert_eq!(sim.desynced(), None);
+    assert_eq!(sim.checksum(), replay.checksum());
+}
+
+#[test]
+fn replays_which_cant_be_followed_are_desynced() {
+    let mut sim = play(3);
+    // building twice at the same place can't work
+    let build = sim.history()[0].1;
+    sim.play(vec![(sim.tick(), build)]);
+    sim.step();
+    assert_eq!(sim.desynced(), Some(60 * 60));
+}
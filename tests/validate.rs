@@ -0,0 +1,188 @@
+use tower_def::sim::{
+    load_level, tileset_tile, validate_map, Coord, Level, LevelLoadError, MapError, RunnerKind,
+    Wave, WaveGroup, Waves,
+};
+
+use std::path::Path;
+
+mod common;
+use common::level;
+use common::tmx::{tmx, tmx_from};
+
+fn validate(tmx: &str) -> Vec<MapError> {
+    validate_map(&tiled::parse(tmx.as_bytes()).unwrap())
+}
+
+#[test]
+fn playable_level() {
+    let map = tiled::parse(tmx(&["B.B..", "S---E"]).as_bytes()).unwrap();
+    assert_eq!(validate_map(&map), vec![]);
+    let level = Level::from_tiled(&map).unwrap();
+    assert_eq!(level.roads().len(), 1);
+    assert_eq!(level.build_points().len(), 2);
+}
+
+#[test]
+fn start_and_end_are_required() {
+    assert_eq!(
+        validate(&tmx(&["-----"])),
+        vec![MapError::NoStart, MapError::NoEnd]
+    );
+}
+
+#[test]
+fn spawn_and_exit_ids_are_unique() {
+    // every tile of the tileset's start point gets its spawn-id
+    let map = tmx(&["S---E", "S---E"]).replace(
+        r#"<property name="start-point" type="bool" value="true"/>"#,
+        r#"<property name="start-point" type="bool" value="true"/>
+           <property name="spawn-id" type="int" value="0"/>"#,
+    );
+    assert_eq!(validate(&map), vec![MapError::DuplicateSpawn(0)]);
+    let map = tmx(&["S---E", "S---E"]).replace(
+        r#"<property name="end-point" type="bool" value="true"/>"#,
+        r#"<property name="end-point" type="bool" value="true"/>
+           <property name="exit-id" type="int" value="3"/>"#,
+    );
+    assert_eq!(validate(&map), vec![MapError::DuplicateExit(3)]);
+}
+
+#[test]
+fn tiles_come_from_their_tileset() {
+    let map = tiled::parse(tmx_from(&["B.B..", "S---E"], 10).as_bytes()).unwrap();
+    assert_eq!(tileset_tile(&map, 1), Some((0, 0)));
+    assert_eq!(tileset_tile(&map, 12), Some((1, 2)));
+    let level = Level::from_tiled(&map).unwrap();
+    assert_eq!(level.roads().len(), 1);
+    assert_eq!(level.build_points().len(), 2);
+}
+
+#[test]
+fn tiles_have_to_be_in_a_tileset() {
+    let map = tmx(&[".S---E"]).replace("firstgid=\"1\"", "firstgid=\"2\"");
+    assert_eq!(
+        validate(&map),
+        vec![MapError::UnknownTile {
+            pos: Coord::new(0, 0),
+            gid: 1
+        }]
+    );
+}
+
+#[test]
+fn tilesets_need_an_image() {
+    let map = tmx(&["S---E"]).replace(r#"<image source="tiles.png" width="128" height="16"/>"#, "");
+    assert_eq!(
+        validate(&map),
+        vec![MapError::TilesetWithoutImage("tiles".to_string())]
+    );
+}
+
+#[test]
+fn unknown_properties_are_reported() {
+    let map = tmx(&["S---E"]).replace("construction-point", "build-point");
+    assert_eq!(
+        validate(&map),
+        vec![MapError::UnknownProperty {
+            tileset: "tiles".to_string(),
+            tile: 7,
+            name: "build-point".to_string()
+        }]
+    );
+}
+
+#[test]
+fn roads_need_a_direction() {
+    // positions count from the top left corner, like in Tiled
+    assert_eq!(
+        validate(&tmx(&["S-x-E", "....."])),
+        vec![MapError::RoadWithoutDirection(Coord::new(2, 0))]
+    );
+}
+
+#[test]
+fn build_points_need_a_road_nearby() {
+    assert_eq!(
+        validate(&tmx(&["S---E", ".....", "B...B"])),
+        vec![
+            MapError::IsolatedBuildPoint(Coord::new(0, 2)),
+            MapError::IsolatedBuildPoint(Coord::new(4, 2)),
+        ]
+    );
+}
+
+#[test]
+fn branches_have_to_lead_to_an_exit() {
+    // the branch going down leaves the map
+    assert_eq!(
+        validate(&tmx(&["S-T-E", "..v..", "..v.."])),
+        vec![
+            MapError::DeadEnd(Coord::new(2, 2)),
+            MapError::DeadEnd(Coord::new(2, 1)),
+        ]
+    );
+}
+
+#[test]
+fn roads_have_to_be_reachable() {
+    assert_eq!(
+        validate(&tmx(&["S---E", ".....", ".---."])),
+        vec![
+            MapError::DisconnectedRoad(Coord::new(1, 2)),
+            MapError::DisconnectedRoad(Coord::new(2, 2)),
+            MapError::DisconnectedRoad(Coord::new(3, 2)),
+        ]
+    );
+}
+
+#[test]
+fn unreachable_exit() {
+    assert_eq!(
+        validate(&tmx(&["S.--E"])),
+        vec![
+            MapError::NoPathToExit(Coord::new(0, 0)),
+            MapError::DisconnectedRoad(Coord::new(2, 0)),
+            MapError::DisconnectedRoad(Coord::new(3, 0)),
+        ]
+    );
+}
+
+#[test]
+fn loading_errors_are_returned() {
+    match load_level(Path::new("assets/missing.tmx")) {
+        Err(LevelLoadError::Io(_)) => {}
+        other => panic!("expected an io error, got {:?}", other),
+    }
+}
+
+#[test]
+fn waves_come_from_spawns_of_the_map() {
+    let waves = Waves::new(vec![Wave::new(
+        1.0,
+        vec![
+            WaveGroup::new(RunnerKind::Grunt, 1, 1.0, Some(0)),
+            WaveGroup::new(RunnerKind::Grunt, 1, 1.0, Some(3)),
+        ],
+    )]);
+    assert_eq!(
+        waves.validate(&level()),
+        vec![MapError::UnknownSpawn { wave: 0, spawn: 3 }]
+    );
+}
+
+#[test]
+fn levels_without_waves_get_generated_ones() {
+    let waves = Waves::for_level(Path::new("assets/missing.tmx"), &level()).unwrap();
+    assert_eq!(waves.len(), 10);
+}
+
+#[test]
+fn broken_waves_are_returned() {
+    let dir = std::env::temp_dir().join("tower-def-broken-waves");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("level.ron"), "[(delay: 5.0, groups: [(count: ").unwrap();
+    match Waves::for_level(&dir.join("level.tmx"), &level()) {
+        Err(LevelLoadError::Waves(_)) => {}
+        other => panic!("expected a waves error, got {:?}", other),
+    }
+}